@@ -0,0 +1,90 @@
+//! Compares backfilling a history of frames over a loopback connection
+//! by sending every frame with [`Client::send_strings`] versus [`Client::send_bulk`].
+//!
+//! Run with `cargo bench --bench backfill`.
+use std::time::{Duration, Instant};
+
+use arduino_plotter::{protocol::Data, Client};
+use futures_util::StreamExt;
+use http::Uri;
+use tokio::net::TcpListener;
+use tokio_websockets::{ClientBuilder, ServerBuilder};
+
+const FRAMES: usize = 50_000;
+const ITERATIONS: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+enum Backfill {
+    PerFrame,
+    Bulk,
+}
+
+async fn backfill(
+    history: &[Data<String>],
+    backfill: Backfill,
+) -> Result<Duration, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let uri: Uri = format!("ws://{}", listener.local_addr()?).parse()?;
+
+    // the Arduino Serial Plotter UI side counting the received lines
+    let ui = tokio::spawn(async move {
+        let (mut ws_stream, _) = ClientBuilder::from_uri(uri).connect().await?;
+
+        let mut lines = 0;
+        while let Some(message) = ws_stream.next().await {
+            let message = message?;
+            if let Some(text) = message.as_text() {
+                lines += serde_json::from_str::<Vec<String>>(text)
+                    .expect("Data lines")
+                    .len();
+            }
+            if lines == FRAMES {
+                break;
+            }
+        }
+
+        Ok::<_, tokio_websockets::Error>((lines, ws_stream))
+    });
+
+    let (stream, _) = listener.accept().await?;
+    let (ws_sink, _ws_stream) = ServerBuilder::new().accept(stream).await?.split();
+    let client = Client::new(ws_sink);
+
+    let start = Instant::now();
+    match backfill {
+        Backfill::PerFrame => {
+            for frame in history {
+                client.send_strings(&frame.0).await?;
+            }
+        }
+        Backfill::Bulk => client.send_bulk(history).await?,
+    }
+    let (lines, _ws_stream) = ui.await??;
+    let elapsed = start.elapsed();
+
+    assert_eq!(FRAMES, lines, "All lines should be received");
+
+    Ok(elapsed)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let history: Vec<Data<String>> = (0..FRAMES)
+        .map(|i| Data(vec![format!("L1:{i},L2:{}\n", i * 2)]))
+        .collect();
+
+    for mode in [Backfill::PerFrame, Backfill::Bulk] {
+        let mut durations = Vec::with_capacity(ITERATIONS);
+        for _ in 0..ITERATIONS {
+            durations.push(backfill(&history, mode).await?);
+        }
+
+        durations.sort();
+        println!(
+            "{mode:?}: median {:?} ({FRAMES} frames per iteration)",
+            durations[ITERATIONS / 2]
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! Measures the receive hot path of the [`Server`] stream, i.e. how many
+//! [`ClientCommand`]s per second are received and parsed over a loopback connection.
+//!
+//! Run with `cargo bench --bench receive`.
+//!
+//! [`Server`]: arduino_plotter::Server
+//! [`ClientCommand`]: arduino_plotter::protocol::ClientCommand
+use std::time::Instant;
+
+use arduino_plotter::Client;
+use futures_util::{SinkExt, StreamExt};
+use http::{HeaderMap, Uri};
+use tokio::net::TcpListener;
+use tokio_websockets::{Message, ServerBuilder};
+
+const MESSAGES: usize = 100_000;
+const ITERATIONS: usize = 5;
+
+async fn receive_all() -> Result<f64, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let uri: Uri = format!("ws://{}", listener.local_addr()?).parse()?;
+
+    // the Arduino Serial Plotter UI side sending the commands
+    let ui = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await?;
+        let mut ws_stream = ServerBuilder::new().accept(stream).await?;
+
+        let command = r#"{"command":"SEND_MESSAGE","data":"L1:1,L2:2\n"}"#;
+        for _ in 0..MESSAGES {
+            ws_stream.feed(Message::text(command.to_string())).await?;
+        }
+        ws_stream.close().await?;
+
+        Ok::<_, tokio_websockets::Error>(ws_stream)
+    });
+
+    let (_client, mut server) = Client::connect_with_headers(uri, HeaderMap::new()).await?;
+
+    let start = Instant::now();
+    let mut received = 0;
+    while let Some(command) = server.next().await {
+        command?;
+        received += 1;
+    }
+    let elapsed = start.elapsed();
+
+    ui.await??;
+    assert_eq!(MESSAGES, received, "All messages should be received");
+
+    Ok(received as f64 / elapsed.as_secs_f64())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut rates = Vec::with_capacity(ITERATIONS);
+    for iteration in 1..=ITERATIONS {
+        let rate = receive_all().await?;
+        println!("iteration {iteration}: {rate:.0} commands/s");
+        rates.push(rate);
+    }
+
+    rates.sort_by(f64::total_cmp);
+    println!(
+        "median: {:.0} commands/s ({MESSAGES} commands per iteration)",
+        rates[ITERATIONS / 2]
+    );
+
+    Ok(())
+}
@@ -9,18 +9,21 @@ use core::time::Duration;
 
 use futures_util::StreamExt;
 use rand::prelude::*;
-use tokio::net::TcpListener;
-use tokio_websockets::{Error, ServerBuilder};
+use tokio_websockets::Error;
 use tracing::{error, info, level_filters::LevelFilter};
 use tracing_subscriber::EnvFilter;
 
 use arduino_plotter::{
     protocol::{ClientCommand, EndOfLine, MonitorModelState, MonitorSettings},
-    Client, Server, ServerError,
+    serve_addr, Client, SendError, Server, ServerError,
 };
 
-async fn run_server_task(mut server: Server, client: Client) {
-    while let Some(value) = server.next().await {
+async fn run_server_task(server: Server, client: Client) {
+    // the UI sends the settings on every change, e.g. while typing,
+    // only the settings after the user has stopped changing them are echoed back
+    let mut commands = server.debounced(Duration::from_millis(200));
+
+    while let Some(value) = commands.next().await {
         match value {
             Ok(message) => {
                 info!("Received message: {message:?}");
@@ -30,29 +33,19 @@ async fn run_server_task(mut server: Server, client: Client) {
                     ClientCommand::ChangeSettings(monitor_settings) => {
                         // if we have an new EndOfLine passed, we need to return it to the UI
                         // in order to get set in the UI as the new value
-                        match monitor_settings.monitor_ui_settings {
-                            Some(MonitorModelState {
-                                line_ending: Some(eol),
-                                ..
-                            }) => {
-                                let eol_result = client
-                                    .set_monitor_settings(MonitorSettings {
-                                        monitor_ui_settings: Some(MonitorModelState {
-                                            line_ending: Some(eol),
-                                            ..Default::default()
-                                        }),
-                                        ..Default::default()
-                                    })
-                                    .await;
-
-                                match eol_result {
-                                    Ok(_) => info!("New End of Line is set: {eol}"),
-                                    Err(err) => {
-                                        error!(?err, "New End of Line was not set in the UI")
-                                    }
+                        if let Some(MonitorModelState {
+                            line_ending: Some(eol),
+                            ..
+                        }) = monitor_settings.monitor_ui_settings
+                        {
+                            let eol_result = client.set_line_ending(eol).await;
+
+                            match eol_result {
+                                Ok(_) => info!("New End of Line is set: {eol}"),
+                                Err(err) => {
+                                    error!(?err, "New End of Line was not set in the UI")
                                 }
                             }
-                            _ => {}
                         }
                     }
                 }
@@ -75,23 +68,20 @@ async fn run_server_task(mut server: Server, client: Client) {
 }
 
 async fn run_client_task(client: Client) {
-    // using existing Client
+    // using existing Client, push the initial settings on connect
     {
-        let settings = MonitorSettings {
-            pluggable_monitor_settings: None,
-            monitor_ui_settings: Some(MonitorModelState {
-                dark_theme: Some(true),
-                connected: Some(true),
-                line_ending: Some(EndOfLine::NewLine),
-                // this will trigger a Close on the currently established connection, do not send!
-                // ws_port: Some(3000),
-                ..Default::default()
-            }),
-        };
+        let settings = MonitorSettings::builder()
+            .dark_theme(true)
+            .connected(true)
+            .line_ending(EndOfLine::NewLine)
+            // changing the `ws_port` closes the currently established connection,
+            // the `Client` refuses to send it
+            // .ws_port(3000)
+            .build();
 
         info!("Monitor Settings to be sent: {settings:?}");
 
-        match client.set_monitor_settings(settings).await {
+        match client.send_full_settings(settings).await {
             Ok(_) => {}
             Err(err) => error!("Failed to set settings: {err}"),
         }
@@ -104,18 +94,19 @@ async fn run_client_task(client: Client) {
             let rand: u32 = rng.gen_range(0..100);
             data.push(rand);
         }
-        let data1_str = format!("L1:{},L2:{},L3:{}\n", data[0], data[1], data[2]);
-        let data2_str = format!("A:{},B:{},C:{}\n", data[3], data[4], data[5]);
-        let data: Vec<&str> = vec![&data1_str, &data2_str];
+        let data = vec![
+            format!("L1:{},L2:{},L3:{}\n", data[0], data[1], data[2]),
+            format!("A:{},B:{},C:{}\n", data[3], data[4], data[5]),
+        ];
 
-        let send_result = client.send(&data).await;
+        let send_result = client.send_strings(&data).await;
         match send_result {
             Ok(_) => info!("Sent data message: {data:?}"),
             Err(err) => {
                 error!("Sending data message failed: {err:?}");
                 if matches!(
                     err,
-                    Error::AlreadyClosed | Error::Io(_) | Error::CannotResolveHost
+                    SendError::ClientClosed | SendError::Ws(Error::CannotResolveHost)
                 ) {
                     // stop current task for current connection
                     break;
@@ -135,23 +126,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing_subscriber::fmt().with_env_filter(env_filter).init();
 
-    let listener = TcpListener::bind("127.0.0.1:3030").await?;
-
-    loop {
-        while let Ok((stream, _plotter_addr)) = listener.accept().await {
-            let ws_stream = match ServerBuilder::new().accept(stream).await {
-                Ok(x) => x,
-                Err(err) => {
-                    error!("Error performing HTTP upgrade handshake request: {err}");
-                    continue;
-                }
-            };
+    serve_addr("127.0.0.1:3030", |client, server| async move {
+        tokio::spawn(run_client_task(client.clone()));
+        run_server_task(server, client).await;
+    })
+    .await?;
 
-            let (ws_sink, ws_stream) = ws_stream.split();
-            let (client, server) = (Client::new(ws_sink), Server::new(ws_stream));
-
-            tokio::spawn(run_server_task(server, client.clone()));
-            tokio::spawn(run_client_task(client));
-        }
-    }
+    Ok(())
 }
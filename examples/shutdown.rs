@@ -0,0 +1,107 @@
+//! This example serves multiple Arduino Serial Plotter connections and shows
+//! how to shut all of them down gracefully:
+//!
+//! - Every connection sends random data every ~1 sec until the shutdown is requested
+//! - On shutdown every connected UI receives `connected: false` and a Close frame
+//! - The example exits once all connections have finished
+//!
+//! Press `Enter` (or close the stdin) to request the shutdown.
+//! To shut down on Ctrl-C instead, enable the `signal` feature of `tokio` and
+//! replace the body of `shutdown_signal` with `tokio::signal::ctrl_c().await`.
+//!
+//! By default, `tracing` will run with TRACE level or you can use the `RUST_LOG` env. variable
+//! to override the default level.
+use core::time::Duration;
+
+use futures_util::StreamExt;
+use rand::prelude::*;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::TcpListener,
+};
+use tracing::{error, info, level_filters::LevelFilter};
+use tracing_subscriber::EnvFilter;
+
+use arduino_plotter::{
+    protocol::{EndOfLine, MonitorSettings},
+    serve, CancellationToken, Client, ConnectionGuard, Server,
+};
+
+async fn shutdown_signal() {
+    let mut line = String::new();
+    let _ = BufReader::new(tokio::io::stdin())
+        .read_line(&mut line)
+        .await;
+}
+
+async fn handle_connection(client: Client, mut server: Server, guard: ConnectionGuard) {
+    info!(id = guard.id(), peer_addr = %guard.peer_addr(), "New connection");
+
+    let settings = MonitorSettings::builder()
+        .connected(true)
+        .line_ending(EndOfLine::NewLine)
+        .build();
+    if let Err(err) = client.send_full_settings(settings).await {
+        error!("Failed to set settings: {err}");
+        return;
+    }
+
+    let server_fut = async {
+        while let Some(result) = server.next().await {
+            info!(
+                id = guard.id(),
+                "Client command received result: {result:?}"
+            )
+        }
+    };
+
+    let client_fut = async {
+        loop {
+            let (l1, l2): (u32, u32) = {
+                let mut rng = rand::thread_rng();
+                (rng.gen_range(0..100), rng.gen_range(0..100))
+            };
+
+            if let Err(err) = client.send_owned(vec![format!("L1:{l1},L2:{l2}\n")]).await {
+                error!(id = guard.id(), "Sending data message failed: {err:?}");
+                break;
+            }
+
+            tokio::select! {
+                _ = guard.cancelled() => break,
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+            }
+        }
+    };
+
+    // the server stream ends once the UI has responded to the Close frame
+    futures_util::join!(server_fut, client_fut);
+    info!(id = guard.id(), "Connection closed");
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::TRACE.into())
+        .from_env_lossy();
+
+    tracing_subscriber::fmt().with_env_filter(env_filter).init();
+
+    let listener = TcpListener::bind("127.0.0.1:3030").await?;
+    let shutdown = CancellationToken::new();
+
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+
+        async move {
+            shutdown_signal().await;
+            info!("Shutdown requested");
+            shutdown.cancel();
+        }
+    });
+
+    serve(listener, shutdown, handle_connection).await;
+    info!("All connections have been closed, exiting");
+
+    Ok(())
+}
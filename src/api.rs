@@ -1,15 +1,41 @@
-use std::{sync::Arc, task::Poll};
+use core::time::Duration;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Arc, PoisonError,
+    },
+    task::{Context, Poll},
+};
 
+use bytes::Bytes;
 use futures_util::{
     stream::{SplitSink, SplitStream},
-    FutureExt, SinkExt, Stream, StreamExt,
+    task::noop_waker_ref,
+    Sink, SinkExt, Stream, StreamExt,
 };
+use http::{header, HeaderMap, HeaderName, HeaderValue, Uri};
+use serde::Serialize;
 use thiserror::Error;
-use tokio::{net::TcpStream, sync::Mutex};
-use tokio_websockets::{Error, Message, WebSocketStream};
-use tracing::{debug, trace};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+    sync::{Mutex, Notify},
+};
+use tokio_websockets::{ClientBuilder, CloseCode, Error, Message, WebSocketStream};
 
-use crate::protocol::{ClientCommand, MiddlewareCommand, MonitorSettings};
+use crate::log::{debug, trace};
+use crate::{
+    protocol::{
+        field_count, is_valid_label, validate_consistent, validate_field_counts, ClientCommand,
+        CommandName, Data, DataSchema, EndOfLine, InconsistentColumns, InvalidLabel,
+        MiddlewareCommand, MonitorModelState, MonitorSettings, SchemaMismatch,
+    },
+    transport::{TransportSink, TransportStream},
+    violation::ViolationHandler,
+    Buffered, ClientSink, CloseFrame, Debounced, Dedup, DedupWindow, GenerateEvents, ServerEvents,
+    SlidingWindow, ViolationAction, ViolationKind, WsTransport,
+};
 
 #[derive(Debug, Error)]
 pub enum ServerError {
@@ -19,11 +45,134 @@ pub enum ServerError {
     /// An error occurred during the deserializing of a JSON to a value
     #[error(transparent)]
     Json(#[from] serde_json::Error),
+    /// A received text message couldn't be deserialized, e.g. an invalid JSON
+    /// or a command of a newer webapp version.
+    ///
+    /// The [`Server`] streams yield it instead of [`ServerError::Json`]
+    /// in order to keep the received text, e.g. for logging it.
+    ///
+    /// ```
+    /// use arduino_plotter::{Server, ServerError};
+    /// use futures_util::{SinkExt, StreamExt};
+    /// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let mut ui = ClientBuilder::new().take_over(ui_io);
+    ///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///     let mut server = Server::from_stream(ws_stream);
+    ///
+    ///     let message = r#"{"command": "SEND_MESSAGE", "data": 42}"#;
+    ///     ui.send(Message::text(message.to_string())).await.unwrap();
+    ///
+    ///     match server.next().await.unwrap() {
+    ///         Err(ServerError::Unrecognized { raw, .. }) => assert_eq!(message, raw),
+    ///         other => panic!("Unexpected {other:?}"),
+    ///     }
+    /// }
+    /// ```
+    #[error("Unrecognized message: {source}")]
+    Unrecognized {
+        /// The received text message.
+        raw: String,
+        #[source]
+        source: serde_json::Error,
+    },
     /// WebSocket Message response was not a text one.
     ///
     /// See [`tokio_websockets::Message::as_text`] for more details.
     #[error("Text-based (json) client command is expected from the serial plotter")]
     NonTextMessage,
+    /// The frame is a Data lines message (a JSON array) which has no command name.
+    ///
+    /// See [`peek_command_name`].
+    #[error("The frame is a Data lines message without a command name")]
+    IsDataFrame,
+    /// The buffer of a [`Buffered`] has filled up, i.e. the consumer can't keep up
+    /// with the received commands, see [`Server::with_capacity`].
+    ///
+    /// No command is lost, the buffered ones are yielded right after it.
+    #[error("The buffer of {capacity} received commands is full")]
+    BufferFull { capacity: usize },
+}
+
+/// Parses only the `command` field of a JSON frame, skipping the `data` without decoding it,
+/// e.g. for routing frames in a proxy.
+///
+/// Returns [`ServerError::IsDataFrame`] for Data lines messages (JSON arrays).
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::{peek_command_name, protocol::CommandName, ServerError};
+///
+/// let settings = r#"{"command": "ON_SETTINGS_DID_CHANGE", "data": {"monitorUISettings": {}}}"#;
+/// assert_eq!(
+///     CommandName::OnSettingsDidChange,
+///     peek_command_name(settings).unwrap()
+/// );
+///
+/// // the data is not decoded
+/// let message = r#"{"data": {"not": ["a", "message"]}, "command": "SEND_MESSAGE"}"#;
+/// assert_eq!(CommandName::SendMessage, peek_command_name(message).unwrap());
+///
+/// assert!(matches!(
+///     peek_command_name(r#"["L1:1,L2:2\n"]"#),
+///     Err(ServerError::IsDataFrame)
+/// ));
+/// assert!(matches!(
+///     peek_command_name(r#"{"data": "hello"}"#),
+///     Err(ServerError::Json(_))
+/// ));
+/// ```
+pub fn peek_command_name(json: &str) -> Result<CommandName, ServerError> {
+    use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+    enum Peeked {
+        Command(CommandName),
+        DataFrame,
+    }
+
+    struct PeekVisitor;
+
+    impl<'de> Visitor<'de> for PeekVisitor {
+        type Value = Peeked;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("a command object or an array of data lines")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Peeked, A::Error> {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+
+            Ok(Peeked::DataFrame)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Peeked, A::Error> {
+            let mut command = None;
+            while let Some(key) = map.next_key::<std::borrow::Cow<'de, str>>()? {
+                if key == "command" {
+                    command = Some(map.next_value::<CommandName>()?);
+                } else {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+
+            command
+                .map(Peeked::Command)
+                .ok_or_else(|| de::Error::missing_field("command"))
+        }
+    }
+
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let peeked = serde::Deserializer::deserialize_any(&mut deserializer, PeekVisitor)?;
+    deserializer.end()?;
+
+    match peeked {
+        Peeked::Command(command) => Ok(command),
+        Peeked::DataFrame => Err(ServerError::IsDataFrame),
+    }
 }
 
 /// Server is needed for receiving messages from the plotter app.
@@ -32,21 +181,509 @@ pub enum ServerError {
 /// - `SEND_MESSAGE` - sending message to the board through serial
 /// - `CHANGE_SETTINGS` - settings for [`EndOfLine`] has bee changed in the application
 ///
+/// The stream ends once the websocket is closed or after an IO error of the connection.
+/// The Ping and Pong frames are skipped, see [`Server::events`] for receiving them.
+///
+/// A text frame with a JSON array of command objects is yielded as a separate item
+/// for every command, in order. No released webapp version (up to `0.2.0`) batches commands,
+/// they are accepted for future ones, while any other array, e.g. of data lines (a Data frame),
+/// of numbers or an empty one, fails with [`ServerError::IsDataFrame`].
+///
+/// ```
+/// use arduino_plotter::{protocol::ClientCommand, Server, ServerError};
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let mut ui = ClientBuilder::new().take_over(ui_io);
+///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+///     let mut server = Server::from_stream(ws_stream);
+///
+///     let batch = r#"[
+///         {"command": "SEND_MESSAGE", "data": "first"},
+///         {"command": "SEND_MESSAGE", "data": "second"}
+///     ]"#;
+///     ui.send(Message::text(batch.to_string())).await.unwrap();
+///     ui.send(Message::text(r#"{"command": "SEND_MESSAGE", "data": "third"}"#.to_string()))
+///         .await
+///         .unwrap();
+///     for not_batch in [r#"["L1:1,L2:2\n"]"#, "[]", "[1, 2]"] {
+///         ui.send(Message::text(not_batch.to_string())).await.unwrap();
+///     }
+///
+///     for expected in ["first", "second", "third"] {
+///         let command = server.next().await.unwrap().unwrap();
+///         assert_eq!(ClientCommand::SendMessage(expected.to_string()), command);
+///     }
+///     for _ in 0..3 {
+///         assert!(matches!(
+///             server.next().await,
+///             Some(Err(ServerError::IsDataFrame))
+///         ));
+///     }
+/// }
+/// ```
+///
+/// # Backpressure
+///
+/// The websocket is only read when the stream is polled, one message at a time, so
+/// a slow consumer leaves the received messages in the connection buffers until they fill up
+/// and the UI's writes wait, i.e. the TCP flow control applies the backpressure.
+/// The Arduino Serial Plotter UI only sends a command on a user action, e.g. a changed setting,
+/// while a consumer which can't keep up with them can coalesce the settings with
+/// [`Server::debounced`], or read them ahead into a bounded buffer which signals
+/// when it's full with [`Server::with_capacity`].
+///
 /// Cheap to clone as it has an internal Atomic reference counter ([`Arc`]) for the Websocket Stream
 ///
+/// The [`Server`] is [`Send`] and [`Sync`], so it can be moved to any task or thread,
+/// while its clones share (and split between them) the received commands.
+///
 /// [`EndOfLine`]: crate::protocol::EndOfLine
 #[derive(Debug, Clone)]
 pub struct Server {
-    ws_stream: Arc<Mutex<SplitStream<WebSocketStream<TcpStream>>>>,
+    // polling the stream never blocks, so a synchronous lock is used which,
+    // unlike an async one, doesn't allocate a lock future on every poll
+    state: Arc<std::sync::Mutex<ServerState>>,
+}
+
+#[derive(Debug)]
+struct ServerState {
+    /// `None` once the connection has failed
+    ws_stream: Option<Box<dyn TransportStream>>,
+    /// The JSON of the not yet yielded commands of a batched frame.
+    batched: VecDeque<String>,
+    on_violation: Option<ViolationHandler>,
+    /// The Close frame received from the UI.
+    close_frame: Option<CloseFrame>,
+}
+
+impl ServerState {
+    /// Yields the `decoded` result, unless it's a protocol violation to be reported
+    /// to the [`Server::on_violation`] callback.
+    fn yielded<T>(&self, decoded: Result<T, ServerError>, payload: &str) -> Polled<T> {
+        let violation = match (&decoded, &self.on_violation) {
+            (Err(err), Some(handler)) => ViolationKind::of(err, payload)
+                .map(|kind| (handler.clone(), kind, payload.to_string())),
+            _ => None,
+        };
+
+        match violation {
+            Some(violation) => Polled::Violation(decoded, violation),
+            None => Polled::Poll(Poll::Ready(Some(decoded))),
+        }
+    }
+}
+
+/// A polled result of the websocket, see [`Server::poll_decode`].
+enum Polled<T> {
+    Poll(Poll<Option<Result<T, ServerError>>>),
+    /// A result to be reported to the violation handler, with its kind and payload,
+    /// after the [`ServerState`] lock has been released.
+    Violation(
+        Result<T, ServerError>,
+        (ViolationHandler, ViolationKind, String),
+    ),
 }
+
 impl Server {
-    pub fn new(ws_stream: SplitStream<WebSocketStream<TcpStream>>) -> Self {
+    /// Creates a [`Server`] from the receiving half of a websocket over any stream,
+    /// e.g. a [`TcpStream`], a TLS or a Unix domain socket stream, or a [`tokio::io::duplex`]
+    /// in tests.
+    ///
+    /// The stream type is erased, so the [`Server`] (and [`Client`]) is the same type
+    /// regardless of the underlying stream.
+    ///
+    /// ```
+    /// use arduino_plotter::{protocol::ClientCommand, Client, Server};
+    /// use futures_util::{SinkExt, StreamExt};
+    /// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let mut ui = ClientBuilder::new().take_over(ui_io);
+    ///     let (ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///     let (client, mut server) = (Client::new(ws_sink), Server::new(ws_stream));
+    ///
+    ///     client.send(&["L1:1\n"]).await.unwrap();
+    ///     let data = ui.next().await.unwrap().unwrap();
+    ///     assert_eq!(Some(r#"["L1:1\n"]"#), data.as_text());
+    ///
+    ///     let message = r#"{"command": "SEND_MESSAGE", "data": "hello"}"#;
+    ///     ui.send(Message::text(message.to_string())).await.unwrap();
+    ///     assert_eq!(
+    ///         ClientCommand::SendMessage("hello".to_string()),
+    ///         server.next().await.unwrap().unwrap()
+    ///     );
+    /// }
+    /// ```
+    pub fn new<S>(ws_stream: SplitStream<WebSocketStream<S>>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + core::fmt::Debug + Send + Unpin + 'static,
+    {
+        Self::from_stream(ws_stream)
+    }
+
+    /// Creates a [`Server`] from the receiving half of a [`WsTransport`].
+    pub fn from_stream<S>(ws_stream: S) -> Self
+    where
+        S: Stream<Item = Result<Message, Error>> + core::fmt::Debug + Send + Unpin + 'static,
+    {
         Self {
-            ws_stream: Arc::new(Mutex::new(ws_stream)),
+            state: Arc::new(std::sync::Mutex::new(ServerState {
+                ws_stream: Some(Box::new(ws_stream)),
+                batched: VecDeque::new(),
+                on_violation: None,
+                close_frame: None,
+            })),
+        }
+    }
+
+    /// Reads up to `capacity` received commands ahead, see [`Buffered`].
+    ///
+    /// # Panics
+    ///
+    /// When `capacity` is zero or when not called from a tokio runtime.
+    pub fn with_capacity(self, capacity: usize) -> Buffered {
+        Buffered::new(self, capacity)
+    }
+
+    /// Coalesces the settings received within the `window`, see [`Debounced`].
+    pub fn debounced(self, window: Duration) -> Debounced {
+        Debounced::new(self, window)
+    }
+
+    /// Suppresses the consecutive identical [`ClientCommand`]s, see [`Dedup`].
+    pub fn dedup(self, window: DedupWindow) -> Dedup {
+        Dedup::new(self, window)
+    }
+
+    /// The number of [`Server`] handles (clones) sharing the connection, including this one.
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.state)
+    }
+
+    /// Invokes the `callback` with the [`ViolationKind`] and the received payload
+    /// of every message violating the protocol, before its error is yielded
+    /// or instead of it with [`ViolationAction::Suppress`],
+    /// e.g. for tracking the compatibility issues with the webapp versions.
+    ///
+    /// The callback is shared by all the clones of the [`Server`] and replaces the previous one.
+    /// It's invoked without holding the lock of the connection, so it can use the [`Server`]
+    /// clones, e.g. [`Server::close_frame`].
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use arduino_plotter::{protocol::ClientCommand, Server, ViolationAction, ViolationKind};
+    /// use futures_util::{SinkExt, StreamExt};
+    /// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let mut ui = ClientBuilder::new().take_over(ui_io);
+    ///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///
+    ///     let violations = Arc::new(Mutex::new(Vec::new()));
+    ///     let reported = violations.clone();
+    ///     let server = Server::from_stream(ws_stream);
+    ///     let handle = server.clone();
+    ///     let mut server = server.on_violation(ViolationAction::Suppress, move |kind, payload| {
+    ///         // the Server can be used in the callback
+    ///         assert!(handle.close_frame().is_none());
+    ///         reported.lock().unwrap().push((kind, payload.to_string()))
+    ///     });
+    ///
+    ///     for frame in [
+    ///         r#"{"command": "NEW_COMMAND", "data": 1}"#,
+    ///         "not json",
+    ///         r#"{"command": "SEND_MESSAGE", "data": "hello"}"#,
+    ///     ] {
+    ///         ui.send(Message::text(frame.to_string())).await.unwrap();
+    ///     }
+    ///
+    ///     let command = server.next().await.unwrap().unwrap();
+    ///     assert_eq!(ClientCommand::SendMessage("hello".to_string()), command);
+    ///     assert_eq!(
+    ///         vec![
+    ///             (
+    ///                 ViolationKind::UnknownCommand,
+    ///                 r#"{"command": "NEW_COMMAND", "data": 1}"#.to_string()
+    ///             ),
+    ///             (ViolationKind::InvalidJson, "not json".to_string()),
+    ///         ],
+    ///         *violations.lock().unwrap()
+    ///     );
+    /// }
+    /// ```
+    pub fn on_violation<F>(self, action: ViolationAction, callback: F) -> Self
+    where
+        F: Fn(ViolationKind, &str) + Send + Sync + 'static,
+    {
+        self.lock_state().on_violation = Some(ViolationHandler {
+            callback: Arc::new(callback),
+            action,
+        });
+
+        self
+    }
+
+    /// The Close frame of the UI, once received, e.g. for telling a clean close
+    /// from a failed connection after the [`Server`] stream has ended.
+    ///
+    /// See [`Server::events`] for receiving it as the last event of the stream.
+    pub fn close_frame(&self) -> Option<CloseFrame> {
+        self.lock_state().close_frame.clone()
+    }
+
+    /// Yields the Ping, Pong and Close frames as well, see [`ServerEvents`].
+    pub fn events(self) -> ServerEvents {
+        ServerEvents::new(self)
+    }
+
+    /// Surfaces the changes of the `generate` flag, see [`GenerateEvents`].
+    pub fn generate_events(self) -> GenerateEvents {
+        GenerateEvents::new(self)
+    }
+
+    /// Waits for the initial [`ClientCommand::ChangeSettings`] of the Arduino Serial Plotter UI
+    /// and returns its settings.
+    ///
+    /// The commands received before it are discarded, while a receive error is returned.
+    /// The [`Server`] stream continues with the commands received after the handshake.
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use arduino_plotter::Server;
+    /// use futures_util::StreamExt;
+    ///
+    /// async fn run(mut server: Server) {
+    ///     match server.await_handshake(Duration::from_secs(5)).await {
+    ///         Ok(settings) => println!("Initial settings: {settings:?}"),
+    ///         Err(err) => return println!("Handshake failed: {err}"),
+    ///     }
+    ///
+    ///     while let Some(command) = server.next().await {
+    ///         println!("Received: {command:?}");
+    ///     }
+    /// }
+    /// ```
+    pub async fn await_handshake(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<MonitorSettings, HandshakeError> {
+        let handshake = async {
+            while let Some(result) = self.next().await {
+                match result? {
+                    ClientCommand::ChangeSettings(settings) => {
+                        trace!("Handshake settings received");
+
+                        return Ok(settings);
+                    }
+                    command => debug!(?command, "Command received before the handshake"),
+                }
+            }
+
+            Err(HandshakeError::Closed)
+        };
+
+        tokio::time::timeout(timeout, handshake)
+            .await
+            .unwrap_or(Err(HandshakeError::Timeout))
+    }
+
+    /// Receives the next [`ClientCommand`] paired with the exact received JSON text,
+    /// e.g. for forwarding or logging the frames in a debugging proxy.
+    ///
+    /// Same as the [`Server`] stream, it returns `None` once the websocket is closed.
+    /// The commands of a batched frame are paired with their own (re-serialized) JSON.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::Server;
+    ///
+    /// async fn proxy(server: Server) {
+    ///     while let Some(result) = server.next_with_raw().await {
+    ///         match result {
+    ///             Ok((command, raw)) => println!("Received {raw} as {command:?}"),
+    ///             Err(err) => println!("Receiving failed: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn next_with_raw(&self) -> Option<Result<(ClientCommand, String), ServerError>> {
+        futures_util::future::poll_fn(|cx| {
+            self.poll_decode(
+                cx,
+                |text_payload| {
+                    let command = serde_json::from_str::<ClientCommand>(text_payload)?;
+
+                    Ok((command, text_payload.to_string()))
+                },
+                |_| None,
+            )
+        })
+        .await
+    }
+
+    /// Polls the next text message of the websocket and decodes it.
+    ///
+    /// The Ping and Pong frames are passed to `control` and skipped when it returns `None`.
+    pub(crate) fn poll_decode<T>(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        decode: impl Fn(&str) -> Result<T, ServerError>,
+        control: impl Fn(&Message) -> Option<T>,
+    ) -> Poll<Option<Result<T, ServerError>>> {
+        loop {
+            match self.poll_locked(cx, &decode, &control) {
+                Polled::Poll(poll) => return poll,
+                Polled::Violation(decoded, (handler, kind, payload)) => {
+                    // the callback is invoked without the lock, so it can use the Server
+                    if !handler.report(kind, &payload) {
+                        return Poll::Ready(Some(decoded));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Locks the connection state, which stays consistent even when
+    /// a panic has poisoned the lock.
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, ServerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Polls the websocket with the connection state locked until a result is ready
+    /// or a protocol violation has to be reported.
+    fn poll_locked<T>(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        decode: &impl Fn(&str) -> Result<T, ServerError>,
+        control: &impl Fn(&Message) -> Option<T>,
+    ) -> Polled<T> {
+        let mut guard = self.lock_state();
+        let state = &mut *guard;
+
+        loop {
+            if let Some(command) = state.batched.pop_front() {
+                let decoded = with_raw(decode(&command), &command);
+                return state.yielded(decoded, &command);
+            }
+
+            let Some(ws_stream) = state.ws_stream.as_mut() else {
+                return Polled::Poll(Poll::Ready(None));
+            };
+
+            let polled = match ws_stream.poll_next_unpin(cx) {
+                Poll::Ready(polled) => polled,
+                Poll::Pending => return Polled::Poll(Poll::Pending),
+            };
+            let message = match polled {
+                Some(Ok(message)) => message,
+                Some(Err(err)) => {
+                    // the websocket keeps failing with the same IO error,
+                    // so the stream ends after it
+                    if matches!(err, Error::Io(_)) {
+                        debug!(?err, "Websocket connection failed");
+                        state.ws_stream = None;
+                    }
+
+                    return Polled::Poll(Poll::Ready(Some(Err(ServerError::Ws(err)))));
+                }
+                None => return Polled::Poll(Poll::Ready(None)),
+            };
+
+            if message.is_close() {
+                // `Message::as_close` causes an unsafe precondition panic on Rust 1.78,
+                // so the payload is parsed without it
+                let close_frame = CloseFrame::parse(message.as_payload());
+                debug!(?close_frame, "Websocket closed");
+                state.close_frame = Some(close_frame);
+
+                // keep polling the websocket in order to flush the Close frame reply,
+                // the websocket stream ends right after it
+                continue;
+            }
+
+            if message.is_ping() || message.is_pong() {
+                // the websocket replies to the Pings by itself
+                trace!(ping = message.is_ping(), "Ping/Pong WS message received");
+                match control(&message) {
+                    Some(event) => return Polled::Poll(Poll::Ready(Some(Ok(event)))),
+                    None => continue,
+                }
+            }
+
+            let Some(text_payload) = message.as_text() else {
+                let payload = String::from_utf8_lossy(message.as_payload());
+                return state.yielded(Err(ServerError::NonTextMessage), &payload);
+            };
+            trace!(text_payload, "Text WS message received");
+
+            // only a frame starting with an array can be a batch of commands
+            if text_payload.trim_start().starts_with('[') {
+                match unbatch(text_payload) {
+                    Ok(commands) => {
+                        trace!(count = commands.len(), "Batched commands received");
+                        state.batched.extend(commands);
+                        continue;
+                    }
+                    Err(err) => {
+                        return state.yielded(with_raw(Err(err), text_payload), text_payload)
+                    }
+                }
+            }
+
+            // the received text is already validated UTF-8 borrowed from the payload
+            let decoded = with_raw(decode(text_payload), text_payload);
+            return state.yielded(decoded, text_payload);
         }
     }
 }
 
+/// Keeps the `payload` of a [`ServerError::Json`] as a [`ServerError::Unrecognized`].
+fn with_raw<T>(decoded: Result<T, ServerError>, payload: &str) -> Result<T, ServerError> {
+    decoded.map_err(|err| match err {
+        ServerError::Json(source) => ServerError::Unrecognized {
+            raw: payload.to_string(),
+            source,
+        },
+        err => err,
+    })
+}
+
+/// Splits a frame with an array of commands into the JSON of every command.
+///
+/// Only a non-empty array of objects is a batch, any other array is a [`ServerError::IsDataFrame`].
+fn unbatch(text_payload: &str) -> Result<Vec<String>, ServerError> {
+    let elements = serde_json::from_str::<Vec<serde_json::Value>>(text_payload)?;
+
+    if elements.is_empty() || !elements.iter().all(serde_json::Value::is_object) {
+        return Err(ServerError::IsDataFrame);
+    }
+
+    Ok(elements.iter().map(ToString::to_string).collect())
+}
+
+/// Errors when waiting for the initial settings with [`Server::await_handshake`].
+#[derive(Debug, Error)]
+pub enum HandshakeError {
+    /// Receiving a command failed.
+    #[error(transparent)]
+    Server(#[from] ServerError),
+    /// The UI did not send its settings in time.
+    #[error("Settings handshake was not received in time")]
+    Timeout,
+    /// The connection was closed before the settings were received.
+    #[error("Connection closed before the settings handshake")]
+    Closed,
+}
+
 impl Stream for Server {
     type Item = Result<ClientCommand, ServerError>;
 
@@ -54,89 +691,1785 @@ impl Stream for Server {
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        let mut pin = Box::pin(self.ws_stream.lock());
-        match pin.poll_unpin(cx) {
-            Poll::Ready(mut guard) => guard.poll_next_unpin(cx).map(|next_value| {
-                next_value.and_then(|result| {
-                    let a = result.map_err(ServerError::Ws).and_then(|message| {
-                        if message.is_close() {
-                            debug!("Websocket closed");
-                            return Ok(None);
-                        }
-
-                        // causes unsafe precondition panic on Rust 1.78
-                        // match message.as_close() {
-                        //     Some((close_code, reason)) => {
-                        //         debug!(?close_code, reason, "Websocket closed");
-                        //         // todo: notify the client for the closed websocket
-                        //         return Ok(None);
-                        //     }
-                        //     None => {}
-                        // }
-
-                        let client_command = message
-                            .as_text()
-                            .ok_or(ServerError::NonTextMessage)
-                            .and_then(|text_payload| {
-                            trace!(text_payload, "Text WS message received");
-
-                            serde_json::from_str::<ClientCommand>(text_payload)
-                                .map_err(ServerError::Json)
-                        })?;
-
-                        Ok(Some(client_command))
-                    });
-
-                    a.transpose()
-                })
-            }),
-            Poll::Pending => Poll::Pending,
+        self.poll_decode(
+            cx,
+            |text_payload| {
+                serde_json::from_str::<ClientCommand>(text_payload).map_err(ServerError::Json)
+            },
+            |_| None,
+        )
+    }
+}
+
+/// Stops the keepalive of [`Client::spawn_keepalive`] when dropped.
+#[derive(Debug)]
+pub struct KeepaliveGuard(tokio::task::AbortHandle);
+
+impl Drop for KeepaliveGuard {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Errors when connecting to a websocket with [`Client::connect_with_headers`].
+#[derive(Debug, Error)]
+pub enum ConnectError {
+    /// A Websocket Error occurred
+    #[error(transparent)]
+    Ws(#[from] tokio_websockets::Error),
+    /// The header is set by the websocket handshake and can't be overridden.
+    ///
+    /// See [`Client::FORBIDDEN_HEADERS`].
+    #[error("Header {0} is set by the websocket handshake and can't be overridden")]
+    ForbiddenHeader(HeaderName),
+}
+
+/// Errors when sending to the Arduino Serial Plotter UI with a [`Client`].
+///
+/// Both data and settings messages share the same errors.
+///
+/// ```
+/// use arduino_plotter::{protocol::MonitorSettings, Client, SendError};
+/// use futures_util::StreamExt;
+/// use tokio::net::TcpListener;
+/// use tokio_websockets::{ClientBuilder, ServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+///     let uri = format!("ws://{}", listener.local_addr().unwrap());
+///
+///     let ui = tokio::spawn(async move {
+///         ClientBuilder::new()
+///             .uri(&uri)
+///             .unwrap()
+///             .connect()
+///             .await
+///             .unwrap()
+///     });
+///
+///     let (stream, _) = listener.accept().await.unwrap();
+///     let (ws_sink, _ws_stream) = ServerBuilder::new().accept(stream).await.unwrap().split();
+///     let _ui = ui.await.unwrap();
+///
+///     let client = Client::new(ws_sink);
+///     client.close().await.unwrap();
+///
+///     let err = client
+///         .send_full_settings(MonitorSettings::default())
+///         .await
+///         .unwrap_err();
+///     assert!(matches!(err, SendError::ClientClosed));
+/// }
+/// ```
+#[derive(Debug, Error)]
+pub enum SendError {
+    /// The connection has been closed, nothing can be sent anymore.
+    ///
+    /// Classifies the [`tokio_websockets::Error::AlreadyClosed`] and
+    /// [`tokio_websockets::Error::Io`] errors.
+    #[error("The connection to the Arduino Serial Plotter UI has been closed")]
+    ClientClosed,
+    /// A Websocket Error occurred
+    ///
+    /// The websocket rejected the message before writing it, e.g. a too long payload,
+    /// so the [`Client`] stays open, unlike the errors classified as [`SendError::ClientClosed`].
+    #[error(transparent)]
+    Ws(tokio_websockets::Error),
+    /// The data frame is inconsistent, see [`Client::set_frame_validation`].
+    #[error(transparent)]
+    InconsistentColumns(#[from] InconsistentColumns),
+    /// The values don't match their labels, see [`Client::send_parallel`].
+    #[error(transparent)]
+    SchemaMismatch(#[from] SchemaMismatch),
+    /// A label would corrupt the sent line, nothing has been sent,
+    /// see [`Client::send_parallel`].
+    #[error(transparent)]
+    InvalidLabel(#[from] InvalidLabel),
+    /// The message couldn't be sent in time, see [`Client::send_timeout`].
+    #[error("Sending to the Arduino Serial Plotter UI has timed out")]
+    Timeout,
+    /// The settings change the websocket port, which makes the UI close the current connection,
+    /// nothing has been sent.
+    ///
+    /// See [`Client::set_monitor_settings_allow_port_change`].
+    #[error("Changing the websocket port to {ws_port} would disconnect the UI")]
+    PortChangeWouldDisconnect { ws_port: u16 },
+}
+
+impl From<tokio_websockets::Error> for SendError {
+    fn from(err: tokio_websockets::Error) -> Self {
+        match err {
+            Error::AlreadyClosed => Self::ClientClosed,
+            Error::Io(err) => {
+                debug!(?err, "Connection closed by an IO error");
+                Self::ClientClosed
+            }
+            err => Self::Ws(err),
         }
     }
 }
 
+/// Errors when sending frames with [`Client::send_schema_checked`].
+#[derive(Debug, Error)]
+pub enum SchemaError {
+    /// Sending the frames failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+    /// A frame does not match the [`DataSchema`], nothing has been sent.
+    #[error(transparent)]
+    SchemaMismatch(#[from] SchemaMismatch),
+}
+
+/// Errors when sending a Data lines message with [`Client::try_send`].
+#[derive(Debug, Error)]
+pub enum TrySendError {
+    /// The websocket can't accept the message without waiting, nothing has been sent.
+    ///
+    /// The caller can drop the message or buffer it and retry later.
+    #[error("The websocket is not ready to send the message")]
+    NotReady,
+    /// Sending the message failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+}
+
+/// The websocket frame type of a raw payload sent with [`Client::send_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    /// A text frame, the payload must be valid UTF-8.
+    Text,
+    /// A binary frame.
+    Binary,
+}
+
 /// Client for sending Data message or [`MiddlewareCommand`] (i.e. [`MonitorSettings`])
 ///
 /// Cheap to clone as it has an internal Atomic reference counter ([`Arc`]) for the Websocket Stream
+///
+/// The [`Client`] is [`Send`] and [`Sync`], so its clones can be moved to and
+/// used concurrently from any task or thread.
+///
+/// # Ordering
+///
+/// When multiple clones send concurrently, control messages (settings and closing)
+/// take priority over data messages:
+/// - a control message is sent before any data message which hasn't started sending yet,
+///   i.e. it waits at most for the data message which is currently being sent
+/// - control messages are sent in the order they were requested
+/// - data messages are sent in the order they were requested
+///
+/// A constant flood of control messages will delay the data messages.
 #[derive(Debug, Clone)]
 pub struct Client {
-    ws_sink: Arc<Mutex<SplitSink<WebSocketStream<TcpStream>, Message>>>,
+    inner: Arc<ClientInner>,
+}
+
+// the handles are shared across tasks, make sure they stay thread-safe
+const _: () = {
+    const fn assert_thread_safe<T: Send + Sync + Clone>() {}
+
+    assert_thread_safe::<Client>();
+    assert_thread_safe::<Server>();
+};
+
+#[derive(Debug)]
+struct ClientInner {
+    ws_sink: Mutex<Box<dyn TransportSink>>,
+    /// The number of control messages waiting to be sent.
+    pending_control: AtomicUsize,
+    /// Notifies the waiting data messages that a control message has been sent.
+    control_sent: Notify,
+    paused: AtomicBool,
+    /// Data frames are validated with [`Data::validate_consistent`] before sending.
+    validate_frames: AtomicBool,
+    /// The connection has been closed or sending has failed.
+    closed: AtomicBool,
+    /// Notifies the waiting data messages that the client has been resumed.
+    resumed: Notify,
+    debounced_settings: std::sync::Mutex<DebouncedSettings>,
+    /// The websocket port the UI is connected to, `0` when it's not known yet.
+    ws_port: AtomicU32,
+}
+
+/// The settings waiting to be sent by [`Client::set_settings_debounced`].
+#[derive(Debug, Default)]
+struct DebouncedSettings {
+    pending: Option<MonitorSettings>,
+    /// A call is waiting for the debounce window to send the pending settings.
+    scheduled: bool,
+}
+
+impl ClientInner {
+    /// Locks the debounced settings, which stay consistent even when
+    /// a panic has poisoned the lock.
+    fn lock_debounced(&self) -> std::sync::MutexGuard<'_, DebouncedSettings> {
+        self.debounced_settings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Allows the next debounced settings to schedule a send when the scheduled call is cancelled.
+struct DebounceScheduled<'a>(&'a ClientInner);
+
+impl Drop for DebounceScheduled<'_> {
+    fn drop(&mut self) {
+        self.0.lock_debounced().scheduled = false;
+    }
+}
+
+/// Decrements the pending control messages when the control message has been sent or cancelled.
+struct PendingControl<'a>(&'a ClientInner);
+
+impl<'a> PendingControl<'a> {
+    fn new(inner: &'a ClientInner) -> Self {
+        inner.pending_control.fetch_add(1, Ordering::AcqRel);
+
+        Self(inner)
+    }
+}
+
+impl Drop for PendingControl<'_> {
+    fn drop(&mut self) {
+        self.0.pending_control.fetch_sub(1, Ordering::AcqRel);
+        self.0.control_sent.notify_waiters();
+    }
+}
+
+/// Combines all the values of the `name` header into a single value.
+fn combined_value(name: &HeaderName, headers: &HeaderMap) -> HeaderValue {
+    let mut values = headers.get_all(name).iter();
+    let first = values.next().expect("The header should have a value");
+
+    let separator: &[u8] = if name == header::COOKIE { b"; " } else { b", " };
+    let combined = values.fold(first.as_bytes().to_vec(), |mut combined, value| {
+        combined.extend_from_slice(separator);
+        combined.extend_from_slice(value.as_bytes());
+        combined
+    });
+
+    HeaderValue::from_bytes(&combined).expect("Combined header values should be valid")
+}
+
+/// Serializes a message of the protocol into its JSON.
+pub(crate) fn serialize<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).expect("Should always be serializable!")
+}
+
+/// Serializes the settings into the JSON of a [`MiddlewareCommand`].
+fn settings_json(monitor_settings: MonitorSettings) -> String {
+    let settings = MiddlewareCommand(monitor_settings);
+
+    trace!("Settings to be sent: {settings:?}");
+    let command_json = serialize(&settings);
+    trace!("Settings command JSON to be sent: {command_json:?}");
+
+    command_json
+}
+
+fn settings_ws_port(monitor_settings: &MonitorSettings) -> Option<u16> {
+    monitor_settings
+        .monitor_ui_settings
+        .as_ref()
+        .and_then(|ui_settings| ui_settings.ws_port)
+}
+
+/// Returns the [`MonitorModelState::ws_port`] of the settings when it differs from
+/// the `current` port of the connection (if known), i.e. sending them disconnects the UI.
+pub(crate) fn port_change(current: Option<u16>, monitor_settings: &MonitorSettings) -> Option<u16> {
+    settings_ws_port(monitor_settings).filter(|ws_port| Some(*ws_port) != current)
 }
 
 impl Client {
-    pub fn new(ws_sink: SplitSink<WebSocketStream<TcpStream>, Message>) -> Self {
-        Self {
-            ws_sink: Arc::new(Mutex::new(ws_sink)),
+    /// The headers which are set by the websocket handshake and can't be passed
+    /// to [`Client::connect_with_headers`].
+    pub const FORBIDDEN_HEADERS: &'static [HeaderName] = &[
+        header::HOST,
+        header::UPGRADE,
+        header::CONNECTION,
+        header::SEC_WEBSOCKET_KEY,
+        header::SEC_WEBSOCKET_VERSION,
+    ];
+
+    /// The maximum number of lines packed into a single message by [`Client::send_bulk`].
+    pub const BULK_MESSAGE_LINES: usize = 1_000;
+
+    /// Connects to a websocket at the `ws://` `uri`, passing the additional HTTP `headers`
+    /// in the handshake request, e.g. an `Authorization` header for an authenticated proxy.
+    ///
+    /// Returns the [`Client`] and [`Server`] of the established connection.
+    ///
+    /// The [`Client::FORBIDDEN_HEADERS`] are set by the handshake itself and
+    /// passing any of them results in a [`ConnectError::ForbiddenHeader`].
+    /// The values of a repeated header are combined into a single header,
+    /// separated by `; ` for `Cookie` and by `, ` for the other headers.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::Client;
+    /// use http::{header, HeaderMap, HeaderValue, Uri};
+    ///
+    /// # async fn connect() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer token"));
+    ///
+    /// let uri = Uri::from_static("ws://gateway.local:3030");
+    /// let (client, server) = Client::connect_with_headers(uri, headers).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ```
+    /// use arduino_plotter::Client;
+    /// use http::{header, HeaderMap, HeaderValue, Uri};
+    /// use tokio::{io::AsyncReadExt, net::TcpListener};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let uri: Uri = format!("ws://{}", listener.local_addr().unwrap())
+    ///         .parse()
+    ///         .unwrap();
+    ///
+    ///     let mut headers = HeaderMap::new();
+    ///     headers.append(header::COOKIE, HeaderValue::from_static("session=1"));
+    ///     headers.append(header::COOKIE, HeaderValue::from_static("theme=dark"));
+    ///     headers.append(header::SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static("plotter"));
+    ///     headers.append(header::SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static("json"));
+    ///     tokio::spawn(Client::connect_with_headers(uri, headers));
+    ///
+    ///     // read the handshake request
+    ///     let (mut stream, _) = listener.accept().await.unwrap();
+    ///     let mut request = Vec::new();
+    ///     while !request.ends_with(b"\r\n\r\n") {
+    ///         let mut buf = [0; 1024];
+    ///         let read = stream.read(&mut buf).await.unwrap();
+    ///         request.extend_from_slice(&buf[..read]);
+    ///     }
+    ///
+    ///     let request = String::from_utf8(request).unwrap();
+    ///     assert!(request.contains("cookie: session=1; theme=dark\r\n"));
+    ///     assert!(request.contains("sec-websocket-protocol: plotter, json\r\n"));
+    /// }
+    /// ```
+    pub async fn connect_with_headers(
+        uri: Uri,
+        headers: HeaderMap,
+    ) -> Result<(Client, Server), ConnectError> {
+        if let Some(forbidden) = Self::FORBIDDEN_HEADERS
+            .iter()
+            .find(|forbidden| headers.contains_key(*forbidden))
+        {
+            return Err(ConnectError::ForbiddenHeader(forbidden.clone()));
         }
-    }
 
-    /// Send a [`MonitorSettings`] ([`MiddlewareCommand`]) to the Arduino Serial Plotter UI
-    /// through an already established connection.
-    pub async fn set_monitor_settings(
-        &self,
-        monitor_settings: MonitorSettings,
-    ) -> Result<(), Error> {
-        let settings = MiddlewareCommand(monitor_settings);
+        if uri.scheme_str() != Some("ws") {
+            return Err(Error::UnsupportedScheme.into());
+        }
+        let host = uri.host().ok_or(Error::CannotResolveHost)?;
+        let port = uri.port_u16().unwrap_or(80);
 
-        trace!("Settings to be sent: {settings:?}");
-        let command_json = serde_json::to_string(&settings).unwrap();
-        trace!("Settings command JSON to be sent: {command_json:?}");
+        // IPv6 hosts are enclosed in brackets
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let stream = TcpStream::connect((host, port)).await.map_err(Error::Io)?;
 
-        self.ws_sink
-            .lock()
-            .await
-            .send(Message::text(command_json))
-            .await
+        // the builder replaces the previous value of a header
+        let builder = headers
+            .keys()
+            .fold(ClientBuilder::from_uri(uri.clone()), |builder, name| {
+                builder.add_header(name.clone(), combined_value(name, &headers))
+            });
+        let (ws_stream, _response) = builder.connect_on(stream).await?;
+        debug!(%uri, "Websocket connection established");
+
+        Ok(Client::from_transport(ws_stream))
     }
 
-    /// Send a Data lines message to the Arduino Serial Plotter UI to plot.
-    pub async fn send(&self, data: &[&str]) -> Result<(), Error> {
-        let data_json = serde_json::to_string(data).expect("Should always be serializable!");
+    /// Creates a [`Client`] and a [`Server`] for a connection over any [`WsTransport`].
+    ///
+    /// ```no_run
+    /// use arduino_plotter::Client;
+    /// use tokio::net::TcpListener;
+    /// use tokio_websockets::ServerBuilder;
+    ///
+    /// async fn accept(listener: TcpListener) -> Result<(), tokio_websockets::Error> {
+    ///     let (stream, _) = listener.accept().await?;
+    ///     let ws_stream = ServerBuilder::new().accept(stream).await?;
+    ///
+    ///     let (client, server) = Client::from_transport(ws_stream);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_transport<T: WsTransport>(transport: T) -> (Client, Server) {
+        let (ws_sink, ws_stream) = transport.into_split();
 
-        self.ws_sink
-            .lock()
-            .await
+        (Client::from_sink(ws_sink), Server::from_stream(ws_stream))
+    }
+
+    /// Creates a [`Client`] from the sending half of a websocket over any stream,
+    /// see [`Server::new`].
+    pub fn new<S>(ws_sink: SplitSink<WebSocketStream<S>, Message>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + core::fmt::Debug + Send + Unpin + 'static,
+    {
+        Self::from_sink(ws_sink)
+    }
+
+    /// Creates a [`Client`] from the sending half of a [`WsTransport`].
+    pub fn from_sink<S>(ws_sink: S) -> Self
+    where
+        S: Sink<Message, Error = Error> + core::fmt::Debug + Send + Unpin + 'static,
+    {
+        Self {
+            inner: Arc::new(ClientInner {
+                ws_sink: Mutex::new(Box::new(ws_sink)),
+                pending_control: AtomicUsize::new(0),
+                control_sent: Notify::new(),
+                paused: AtomicBool::new(false),
+                validate_frames: AtomicBool::new(false),
+                closed: AtomicBool::new(false),
+                resumed: Notify::new(),
+                debounced_settings: Default::default(),
+                ws_port: AtomicU32::new(0),
+            }),
+        }
+    }
+
+    /// Send a [`MonitorSettings`] ([`MiddlewareCommand`]) to the Arduino Serial Plotter UI
+    /// through an already established connection.
+    ///
+    /// Settings with a [`MonitorModelState::ws_port`] other than the port the UI is connected to
+    /// are not sent and fail with [`SendError::PortChangeWouldDisconnect`], as the UI reconnects
+    /// to the new port and closes the current connection,
+    /// see [`Client::set_monitor_settings_allow_port_change`].
+    /// The port is known once it has been set with [`Client::set_ws_port`] or sent,
+    /// until then any [`MonitorModelState::ws_port`] is refused.
+    /// The [`Client`]s of [`serve`](crate::serve) and [`PlotterListener`](crate::PlotterListener)
+    /// know the port the UI has connected to.
+    /// All the other methods sending settings, including the ones of the adapters,
+    /// refuse a port change the same way.
+    ///
+    /// ```
+    /// use arduino_plotter::{
+    ///     protocol::{MonitorModelState, MonitorSettings},
+    ///     Client, SendError,
+    /// };
+    /// use futures_util::StreamExt;
+    /// use tokio_websockets::ServerBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (_ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let (ws_sink, _ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///     let client = Client::new(ws_sink);
+    ///
+    ///     let port_change = MonitorSettings {
+    ///         monitor_ui_settings: Some(MonitorModelState {
+    ///             ws_port: Some(3000),
+    ///             ..Default::default()
+    ///         }),
+    ///         ..Default::default()
+    ///     };
+    ///     assert!(matches!(
+    ///         client.set_monitor_settings(port_change.clone()).await,
+    ///         Err(SendError::PortChangeWouldDisconnect { ws_port: 3000 })
+    ///     ));
+    ///
+    ///     client
+    ///         .set_monitor_settings_allow_port_change(port_change.clone())
+    ///         .await
+    ///         .unwrap();
+    ///     // the current port can be sent again, e.g. with the full settings
+    ///     client.send_full_settings(port_change).await.unwrap();
+    /// }
+    /// ```
+    pub async fn set_monitor_settings(
+        &self,
+        monitor_settings: MonitorSettings,
+    ) -> Result<(), SendError> {
+        self.send_settings(monitor_settings).await
+    }
+
+    /// Same as [`Client::set_monitor_settings`] but the settings may change the
+    /// [`MonitorModelState::ws_port`], e.g. when moving the UI to another server on purpose.
+    ///
+    /// Once sent, the port is the current one and can be sent again by the other methods.
+    pub async fn set_monitor_settings_allow_port_change(
+        &self,
+        monitor_settings: MonitorSettings,
+    ) -> Result<(), SendError> {
+        let ws_port = settings_ws_port(&monitor_settings);
+
+        self.send_control(Message::text(settings_json(monitor_settings)))
+            .await?;
+        if let Some(ws_port) = ws_port {
+            self.set_ws_port(ws_port);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the websocket port the Arduino Serial Plotter UI is connected to,
+    /// i.e. the [`MonitorModelState::ws_port`] which the settings can carry without
+    /// disconnecting the UI, see [`Client::set_monitor_settings`].
+    ///
+    /// E.g. when accepting the UI connections on a listener, it's the port of the listener.
+    /// Not to be confused with the port of the [`Client::connect_with_headers`] URI,
+    /// which is the port the [`Client`] has connected to.
+    ///
+    /// ```
+    /// use arduino_plotter::{protocol::MonitorSettings, Client, SendError};
+    /// use futures_util::StreamExt;
+    /// use tokio_websockets::ServerBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (_ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let (ws_sink, _ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///     let client = Client::new(ws_sink);
+    ///     client.set_ws_port(3000);
+    ///
+    ///     let same_port = MonitorSettings::builder().ws_port(3000).build();
+    ///     client.set_monitor_settings(same_port).await.unwrap();
+    ///
+    ///     let other_port = MonitorSettings::builder().ws_port(3001).build();
+    ///     assert!(matches!(
+    ///         client.set_monitor_settings(other_port).await,
+    ///         Err(SendError::PortChangeWouldDisconnect { ws_port: 3001 })
+    ///     ));
+    /// }
+    /// ```
+    pub fn set_ws_port(&self, ws_port: u16) {
+        self.inner.ws_port.store(ws_port.into(), Ordering::Release);
+    }
+
+    /// Same as [`Client::set_monitor_settings`] but fails with [`SendError::Timeout`] when
+    /// the settings couldn't be sent within the `timeout`, see [`Client::send_timeout`].
+    pub async fn set_monitor_settings_timeout(
+        &self,
+        monitor_settings: MonitorSettings,
+        timeout: Duration,
+    ) -> Result<(), SendError> {
+        tokio::time::timeout(timeout, self.set_monitor_settings(monitor_settings))
+            .await
+            .unwrap_or(Err(SendError::Timeout))
+    }
+
+    async fn send_settings(&self, monitor_settings: MonitorSettings) -> Result<(), SendError> {
+        self.check_ws_port(&monitor_settings)?;
+
+        self.send_control(Message::text(settings_json(monitor_settings)))
+            .await
+    }
+
+    /// Fails with [`SendError::PortChangeWouldDisconnect`] when the settings change
+    /// the websocket port the UI is connected to.
+    pub(crate) fn check_ws_port(
+        &self,
+        monitor_settings: &MonitorSettings,
+    ) -> Result<(), SendError> {
+        let current = match self.inner.ws_port.load(Ordering::Acquire) {
+            0 => None,
+            port => u16::try_from(port).ok(),
+        };
+
+        match port_change(current, monitor_settings) {
+            Some(ws_port) => Err(SendError::PortChangeWouldDisconnect { ws_port }),
+            None => Ok(()),
+        }
+    }
+
+    /// Sets only the [`EndOfLine`] of the UI, e.g. when echoing back a changed line ending.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{protocol::EndOfLine, Client, SendError};
+    ///
+    /// async fn setup(client: &Client) -> Result<(), SendError> {
+    ///     client.set_connected(true).await?;
+    ///     client.set_line_ending(EndOfLine::NewLine).await?;
+    ///     client.set_dark_theme(true).await
+    /// }
+    /// ```
+    ///
+    pub async fn set_line_ending(&self, eol: EndOfLine) -> Result<(), SendError> {
+        self.set_ui_settings(MonitorModelState {
+            line_ending: Some(eol),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Sets only the dark theme of the UI.
+    pub async fn set_dark_theme(&self, dark_theme: bool) -> Result<(), SendError> {
+        self.set_ui_settings(MonitorModelState {
+            dark_theme: Some(dark_theme),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Sets only whether the UI shows the board as connected.
+    pub async fn set_connected(&self, connected: bool) -> Result<(), SendError> {
+        self.set_ui_settings(MonitorModelState {
+            connected: Some(connected),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Re-sends `connected: true` settings every `interval` in a spawned task,
+    /// so the connected indicator of the UI doesn't go stale during long periods without data.
+    ///
+    /// It's an application-level heartbeat of settings frames shown by the UI,
+    /// unlike the websocket-level Ping frames of [`Client::spawn_keepalive`] which only keep
+    /// the connection itself alive.
+    /// The first heartbeat is sent right away.
+    ///
+    /// The heartbeat stops once the connection is closed, sending fails or all the [`Client`]s
+    /// of the connection are dropped, or when it's aborted with the returned handle.
+    ///
+    /// # Panics
+    ///
+    /// When `interval` is zero or when not called from a tokio runtime.
+    ///
+    /// ```
+    /// use core::time::Duration;
+    ///
+    /// use arduino_plotter::Client;
+    /// use futures_util::StreamExt;
+    /// use tokio_websockets::{ClientBuilder, ServerBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let mut ui = ServerBuilder::new().serve(ui_io);
+    ///     let (client, _server) = Client::from_transport(ClientBuilder::new().take_over(plotter_io));
+    ///
+    ///     let heartbeat = client.enable_status_heartbeat(Duration::from_millis(10));
+    ///     for _ in 0..2 {
+    ///         let message = ui.next().await.unwrap().unwrap();
+    ///         assert!(message.as_text().unwrap().contains(r#""connected":true"#));
+    ///     }
+    ///
+    ///     client.close().await.unwrap();
+    ///     tokio::time::sleep(Duration::from_millis(30)).await;
+    ///     assert!(heartbeat.is_finished());
+    /// }
+    /// ```
+    pub fn enable_status_heartbeat(&self, interval: Duration) -> tokio::task::AbortHandle {
+        self.spawn_periodic(interval, "Status heartbeat", |client| async move {
+            client.set_connected(true).await
+        })
+        .abort_handle()
+    }
+
+    /// Sends a websocket Ping frame every `interval` in a spawned task, so idle connections
+    /// aren't dropped by NAT timeouts or proxies, and a dropped connection is detected
+    /// before the next send.
+    ///
+    /// The Pings are control messages, see the [`Client`]'s ordering, and the UI's Pongs
+    /// are handled by the websocket. The first Ping is sent right away.
+    ///
+    /// The keepalive stops once the connection is closed, sending fails or all the [`Client`]s
+    /// of the connection are dropped, or when the returned [`KeepaliveGuard`] is dropped.
+    ///
+    /// # Panics
+    ///
+    /// When `interval` is zero or when not called from a tokio runtime.
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use arduino_plotter::Client;
+    ///
+    /// async fn run(client: Client) {
+    ///     let (keepalive, _guard) = client.spawn_keepalive(Duration::from_secs(30));
+    ///
+    ///     // ... plot while the UI is idle
+    ///
+    ///     client.close().await.unwrap();
+    ///     // the keepalive stops on close
+    ///     keepalive.await.unwrap();
+    /// }
+    /// ```
+    pub fn spawn_keepalive(
+        &self,
+        interval: Duration,
+    ) -> (tokio::task::JoinHandle<()>, KeepaliveGuard) {
+        let keepalive = self.spawn_periodic(interval, "Keepalive ping", |client| async move {
+            client.send_control(Message::ping(Bytes::new())).await
+        });
+        let guard = KeepaliveGuard(keepalive.abort_handle());
+
+        (keepalive, guard)
+    }
+
+    /// Spawns a task calling `send` every `interval` until the connection is closed,
+    /// sending fails or all the [`Client`]s are dropped.
+    fn spawn_periodic<F, Fut>(
+        &self,
+        interval: Duration,
+        name: &'static str,
+        send: F,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(Client) -> Fut + Send + 'static,
+        Fut: core::future::Future<Output = Result<(), SendError>> + Send,
+    {
+        // the task doesn't keep the connection alive
+        let inner = Arc::downgrade(&self.inner);
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                ticks.tick().await;
+
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                let client = Client { inner };
+                if client.is_closed() {
+                    break;
+                }
+
+                trace!("{name} to be sent");
+                if let Err(err) = send(client).await {
+                    debug!(?err, "{name} failed");
+                    break;
+                }
+            }
+        })
+    }
+
+    async fn set_ui_settings(&self, ui_settings: MonitorModelState) -> Result<(), SendError> {
+        self.send_settings(MonitorSettings {
+            monitor_ui_settings: Some(ui_settings),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Send a [`MonitorSettings`] to the Arduino Serial Plotter UI coalescing all the
+    /// settings sent within the `window` into a single message, e.g. when a slider is dragged.
+    ///
+    /// The first call waits for the `window` and sends the settings of all the calls
+    /// made in the meantime merged with [`MonitorSettings::merge`].
+    /// The other calls return right away with `Ok(())` and the sending result
+    /// is returned only from the first call.
+    ///
+    /// When the first call is cancelled, the pending settings are sent by the next call.
+    pub async fn set_settings_debounced(
+        &self,
+        monitor_settings: MonitorSettings,
+        window: Duration,
+    ) -> Result<(), SendError> {
+        {
+            let mut debounced = self.inner.lock_debounced();
+            debounced.pending = Some(match debounced.pending.take() {
+                Some(pending) => pending.merge(monitor_settings),
+                None => monitor_settings,
+            });
+
+            if debounced.scheduled {
+                trace!("Settings coalesced with the pending ones");
+                return Ok(());
+            }
+            debounced.scheduled = true;
+        }
+
+        let scheduled = DebounceScheduled(&self.inner);
+        tokio::time::sleep(window).await;
+
+        let pending = {
+            let mut debounced = self.inner.lock_debounced();
+            debounced.scheduled = false;
+            debounced.pending.take()
+        };
+        // already reset, settings sent from now on schedule a new send
+        core::mem::forget(scheduled);
+
+        match pending {
+            Some(pending) => self.send_settings(pending).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Send the complete [`MonitorSettings`] to the Arduino Serial Plotter UI,
+    /// filling in any missing field with [`MonitorSettings::into_full`].
+    ///
+    /// The Arduino Serial Plotter does not have a command for requesting the
+    /// settings, instead it expects them to be pushed on connect:
+    ///
+    /// 1. The Arduino Serial Plotter UI opens a websocket connection to `wsPort`
+    /// 2. The [`Client`] sends the full settings (`ON_SETTINGS_DID_CHANGE`) with this method
+    /// 3. The UI sends `CHANGE_SETTINGS` ([`ClientCommand::ChangeSettings`]) when the user changes a setting
+    ///    which the [`Client`] confirms by sending the changed settings back to the UI.
+    pub async fn send_full_settings(
+        &self,
+        monitor_settings: MonitorSettings,
+    ) -> Result<(), SendError> {
+        self.send_settings(monitor_settings.into_full()).await
+    }
+
+    /// Send a [`MonitorSettings`] immediately followed by a Data lines message,
+    /// e.g. a new baudrate and the first reading with it, as a single logical update.
+    ///
+    /// Both messages are sent under a single acquisition of the websocket, so no message of
+    /// another clone is sent between them.
+    /// The update is sent as a control message, see the [`Client`]'s ordering,
+    /// i.e. it takes priority over the pending data messages and it's sent even when paused.
+    ///
+    /// ```
+    /// use std::{
+    ///     pin::Pin,
+    ///     sync::{Arc, Mutex},
+    ///     task::{Context, Poll},
+    /// };
+    ///
+    /// use arduino_plotter::{
+    ///     protocol::{MonitorModelState, MonitorSettings},
+    ///     Client,
+    /// };
+    /// use futures_util::Sink;
+    /// use tokio_websockets::{Error, Message};
+    ///
+    /// /// Records the sent messages, yielding to the other tasks before every flush.
+    /// #[derive(Debug, Default)]
+    /// struct RecordingSink {
+    ///     sent: Arc<Mutex<Vec<String>>>,
+    ///     yielded: bool,
+    /// }
+    ///
+    /// impl Sink<Message> for RecordingSink {
+    ///     type Error = Error;
+    ///
+    ///     fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+    ///         Poll::Ready(Ok(()))
+    ///     }
+    ///
+    ///     fn start_send(self: Pin<&mut Self>, message: Message) -> Result<(), Error> {
+    ///         let text = message.as_text().unwrap_or_default().to_string();
+    ///         self.sent.lock().unwrap().push(text);
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+    ///         self.yielded = !self.yielded;
+    ///         if self.yielded {
+    ///             cx.waker().wake_by_ref();
+    ///             return Poll::Pending;
+    ///         }
+    ///         Poll::Ready(Ok(()))
+    ///     }
+    ///
+    ///     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+    ///         self.poll_flush(cx)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let sink = RecordingSink::default();
+    /// let sent = sink.sent.clone();
+    /// let client = Client::from_sink(sink);
+    ///
+    /// let settings = MonitorSettings {
+    ///     monitor_ui_settings: Some(MonitorModelState {
+    ///         dark_theme: Some(true),
+    ///         ..Default::default()
+    ///     }),
+    ///     ..Default::default()
+    /// };
+    /// let other = client.clone();
+    /// let (update, others) = tokio::join!(
+    ///     client.send_settings_then_data(settings, &["L1:1\n"]),
+    ///     async {
+    ///         for i in 0..3 {
+    ///             other.send(&[&format!("L2:{i}\n")]).await?;
+    ///         }
+    ///         Ok::<_, arduino_plotter::SendError>(())
+    ///     },
+    /// );
+    /// update.unwrap();
+    /// others.unwrap();
+    ///
+    /// let sent = sent.lock().unwrap();
+    /// let settings_index = sent
+    ///     .iter()
+    ///     .position(|message| message.contains("ON_SETTINGS_DID_CHANGE"))
+    ///     .unwrap();
+    /// assert_eq!(r#"["L1:1\n"]"#, sent[settings_index + 1]);
+    /// assert_eq!(5, sent.len());
+    /// # }
+    /// ```
+    pub async fn send_settings_then_data(
+        &self,
+        monitor_settings: MonitorSettings,
+        data: &[&str],
+    ) -> Result<(), SendError> {
+        let data_json = self.data_json(data)?;
+        self.check_ws_port(&monitor_settings)?;
+
+        let settings_json = settings_json(monitor_settings);
+        trace!(settings_json, data_json, "Settings and data to be sent");
+
+        let _pending = PendingControl::new(&self.inner);
+        let mut ws_sink = self.inner.ws_sink.lock().await;
+        self.ensure_open()?;
+
+        ws_sink
+            .send(Message::text(settings_json))
+            .await
+            .map_err(|err| self.failed(err))?;
+        ws_sink
             .send(Message::text(data_json))
             .await
+            .map_err(|err| self.failed(err))
+    }
+
+    /// Send a Data lines message to the Arduino Serial Plotter UI to plot.
+    ///
+    /// Any lines of borrowed or owned strings can be sent without converting them first,
+    /// e.g. a `&[&str]`, a `Vec<String>` or a [`Data`] frame,
+    /// and they are always sent as a JSON array of strings.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{protocol::Data, Client, SendError};
+    ///
+    /// async fn plot(client: &Client) -> Result<(), SendError> {
+    ///     client.send(&["L1:1,L2:2\n"]).await?;
+    ///
+    ///     let owned = vec![format!("L1:{}\n", 3), format!("L1:{}\n", 4)];
+    ///     client.send(&owned).await?;
+    ///     client.send(owned).await?;
+    ///
+    ///     client.send(&Data(vec!["L1:5\n".to_string()])).await
+    /// }
+    /// ```
+    ///
+    /// The lines are serialized straight from the iterator, without collecting them first,
+    /// and validated on the way when [`Client::set_frame_validation`] is enabled:
+    ///
+    /// ```
+    /// use arduino_plotter::{protocol::Data, Client, SendError};
+    /// use futures_util::StreamExt;
+    /// use tokio_websockets::{ClientBuilder, ServerBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let mut ui = ClientBuilder::new().take_over(ui_io);
+    ///     let (ws_sink, _ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///     let client = Client::new(ws_sink);
+    ///     client.set_frame_validation(true);
+    ///
+    ///     let owned = vec!["L1:1\n".to_string(), "L1:2\n".to_string()];
+    ///     client.send(&owned).await.unwrap();
+    ///     client.send(owned).await.unwrap();
+    ///     client.send(&Data(vec!["L1:1\n", "L1:2\n"])).await.unwrap();
+    ///     for _ in 0..3 {
+    ///         let data = ui.next().await.unwrap().unwrap();
+    ///         assert_eq!(Some(r#"["L1:1\n","L1:2\n"]"#), data.as_text());
+    ///     }
+    ///
+    ///     assert!(matches!(
+    ///         client.send(["L1:1\n", "L1:1,L2:2\n"]).await,
+    ///         Err(SendError::InconsistentColumns(_))
+    ///     ));
+    /// }
+    /// ```
+    pub async fn send<I>(&self, data: I) -> Result<(), SendError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.send_lines(data).await
+    }
+
+    /// Same as [`Client::send`] but fails with [`SendError::Timeout`] when the lines
+    /// couldn't be sent within the `timeout`, e.g. when the UI doesn't read the connection
+    /// and its buffers are full, instead of waiting with the other senders forever.
+    ///
+    /// The `timeout` includes waiting for the other senders.
+    /// A message which has already been queued by the websocket when the timeout expires
+    /// is still written to the connection with the following messages.
+    ///
+    /// ```
+    /// use core::time::Duration;
+    ///
+    /// use arduino_plotter::{Client, SendError};
+    /// use futures_util::StreamExt;
+    /// use tokio_websockets::ServerBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     // the UI never reads the connection
+    ///     let (_ui_io, plotter_io) = tokio::io::duplex(64);
+    ///     let (ws_sink, _ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///     let client = Client::new(ws_sink);
+    ///
+    ///     let lines = vec![format!("L1:{}\n", "1".repeat(128))];
+    ///     let result = client
+    ///         .send_timeout(&lines, Duration::from_millis(50))
+    ///         .await;
+    ///     assert!(matches!(result, Err(SendError::Timeout)));
+    /// }
+    /// ```
+    pub async fn send_timeout<I>(&self, data: I, timeout: Duration) -> Result<(), SendError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        tokio::time::timeout(timeout, self.send(data))
+            .await
+            .unwrap_or(Err(SendError::Timeout))
+    }
+
+    /// Send a [`Data`] frame of any [`Display`](core::fmt::Display) lines
+    /// to the Arduino Serial Plotter UI to plot, e.g. a `Data<f64>` of positional values.
+    ///
+    /// Every line is formatted with its `Display` implementation, as the UI only understands
+    /// a JSON array of strings, so a `Data<f64>` is sent as `["1.5","2"]`, not `[1.5,2.0]`,
+    /// and a line ending should be a part of the line.
+    /// [`Client::send`] sends a `Data<String>` frame without formatting its lines.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{protocol::Data, Client, SendError};
+    ///
+    /// async fn plot(client: &Client, readings: Vec<f64>) -> Result<(), SendError> {
+    ///     client.send_data(&Data(readings)).await
+    /// }
+    /// ```
+    pub async fn send_data<T: core::fmt::Display>(&self, data: &Data<T>) -> Result<(), SendError> {
+        self.send_lines(data.0.iter().map(ToString::to_string))
+            .await
+    }
+
+    /// Send a Data lines message of owned [`String`]s to the Arduino Serial Plotter UI to plot.
+    ///
+    /// An alias of [`Client::send`], which accepts owned lines as well.
+    pub async fn send_strings(&self, lines: &[String]) -> Result<(), SendError> {
+        self.send(lines).await
+    }
+
+    /// Send a Data lines message to the Arduino Serial Plotter UI to plot,
+    /// taking ownership of the lines.
+    ///
+    /// An alias of [`Client::send`], which accepts owned lines as well.
+    pub async fn send_owned(&self, lines: Vec<String>) -> Result<(), SendError> {
+        self.send(lines).await
+    }
+
+    /// Send a best-effort encoding of an XY pair of the `series_name` series
+    /// to the Arduino Serial Plotter UI.
+    ///
+    /// The Arduino Serial Plotter UI (`arduino-serial-plotter-webapp` 0.2) only plots
+    /// time-series, i.e. the X axis is always the sample index, and it doesn't have
+    /// an XY (scatter) mode.
+    /// Instead, the pair is sent as two time-series of a single line, `{series_name}_x` and
+    /// `{series_name}_y`, e.g. `position_x:1.5,position_y:-2\n`.
+    ///
+    /// When the `series_name` is empty or contains a label (`:`) or values (`,`, ` `, `\t`)
+    /// separator, nothing is sent and [`SendError::InvalidLabel`] is returned.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{Client, SendError};
+    ///
+    /// async fn plot(client: Client) -> Result<(), SendError> {
+    ///     for step in 0..100 {
+    ///         let angle = f64::from(step) / 10.0;
+    ///         client.send_xy("position", angle.cos(), angle.sin()).await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn send_xy(&self, series_name: &str, x: f64, y: f64) -> Result<(), SendError> {
+        if !is_valid_label(series_name) {
+            return Err(InvalidLabel(series_name.to_string()).into());
+        }
+
+        let line = format!("{series_name}_x:{x},{series_name}_y:{y}\n");
+
+        self.send_lines(&[line]).await
+    }
+
+    /// Send a single labeled line zipping the parallel `labels` and `values` slices,
+    /// e.g. `temp:21.5,humidity:40\n`.
+    ///
+    /// Returns [`SchemaMismatch::Count`] without sending anything when the slices
+    /// have different lengths and [`SendError::InvalidLabel`] when a label is empty
+    /// or contains a label (`:`) or values (`,`, ` `, `\t`) separator.
+    ///
+    /// ```
+    /// use arduino_plotter::{protocol::SchemaMismatch, Client, SendError};
+    /// use tokio_websockets::ServerBuilder;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let (stream, _ui) = tokio::io::duplex(1024);
+    /// let (client, _server) = Client::from_transport(ServerBuilder::new().serve(stream));
+    ///
+    /// client
+    ///     .send_parallel(&["temp", "humidity"], &[21.5, 40.0])
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let err = client
+    ///     .send_parallel(&["temp", "humidity"], &[21.5])
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(
+    ///     err,
+    ///     SendError::SchemaMismatch(SchemaMismatch::Count {
+    ///         expected: 2,
+    ///         found: 1
+    ///     })
+    /// ));
+    ///
+    /// let err = client
+    ///     .send_parallel(&["air temp", "humidity"], &[21.5, 40.0])
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(err, SendError::InvalidLabel(_)));
+    /// # }
+    /// ```
+    pub async fn send_parallel(&self, labels: &[&str], values: &[f64]) -> Result<(), SendError> {
+        if labels.len() != values.len() {
+            return Err(SchemaMismatch::Count {
+                expected: labels.len(),
+                found: values.len(),
+            }
+            .into());
+        }
+        if let Some(invalid) = labels.iter().find(|label| !is_valid_label(label)) {
+            return Err(InvalidLabel(invalid.to_string()).into());
+        }
+
+        let line = labels
+            .iter()
+            .zip(values)
+            .map(|(label, value)| format!("{label}:{value}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.send_lines(&[line + "\n"]).await
+    }
+
+    /// Send a Data lines message to the Arduino Serial Plotter UI with a line
+    /// for every item mapped to its labeled values by `f`.
+    ///
+    /// Ordering:
+    /// - the lines are in the order of the `items`, i.e. the UI plots them in that order
+    /// - the values of a line are in the order returned by `f`
+    ///
+    /// Items mapped to no values are skipped and no message is sent when all of them are.
+    /// When a label is empty or contains a label (`:`) or values (`,`, ` `, `\t`) separator,
+    /// nothing is sent and [`SendError::InvalidLabel`] is returned.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{Client, SendError};
+    ///
+    /// struct SensorReading {
+    ///     temperature: f64,
+    ///     humidity: f64,
+    /// }
+    ///
+    /// async fn plot(client: Client, readings: &[SensorReading]) -> Result<(), SendError> {
+    ///     // sends `temperature:21.5,humidity:40\n` and `temperature:21.7,humidity:41.5\n`
+    ///     client
+    ///         .send_mapped(readings, |reading| {
+    ///             vec![
+    ///                 ("temperature".to_string(), reading.temperature),
+    ///                 ("humidity".to_string(), reading.humidity),
+    ///             ]
+    ///         })
+    ///         .await
+    /// }
+    /// ```
+    ///
+    /// ```
+    /// use arduino_plotter::{protocol::InvalidLabel, Client, SendError};
+    /// use tokio_websockets::ServerBuilder;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let (stream, _ui) = tokio::io::duplex(1024);
+    /// let (client, _server) = Client::from_transport(ServerBuilder::new().serve(stream));
+    ///
+    /// let err = client
+    ///     .send_mapped(&[21.5], |temp| vec![("air temp".to_string(), *temp)])
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(err, SendError::InvalidLabel(InvalidLabel(label)) if label == "air temp"));
+    /// # }
+    /// ```
+    pub async fn send_mapped<T>(
+        &self,
+        items: &[T],
+        f: impl Fn(&T) -> Vec<(String, f64)>,
+    ) -> Result<(), SendError> {
+        let lines = items
+            .iter()
+            .map(f)
+            .filter(|values| !values.is_empty())
+            .map(|values| {
+                if let Some((invalid, _)) = values.iter().find(|(label, _)| !is_valid_label(label))
+                {
+                    return Err(InvalidLabel(invalid.clone()));
+                }
+
+                let line = values
+                    .iter()
+                    .map(|(label, value)| format!("{label}:{value}"))
+                    .collect::<Vec<_>>()
+                    .join(",");
+
+                Ok(line + "\n")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        self.send_lines(&lines).await
+    }
+
+    /// Send many [`Data`] frames, e.g. when backfilling the history of a newly
+    /// connected Arduino Serial Plotter UI.
+    ///
+    /// The UI plots every line of a Data lines message on its own, so the lines of
+    /// consecutive frames are packed, in order, into messages of up to
+    /// [`Client::BULK_MESSAGE_LINES`] lines which is much faster than sending
+    /// every frame as a separate message.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{protocol::Data, Client, SendError};
+    ///
+    /// async fn backfill(client: Client, history: &[Data<String>]) -> Result<(), SendError> {
+    ///     client.send_bulk(history).await
+    /// }
+    /// ```
+    pub async fn send_bulk(&self, frames: &[Data<String>]) -> Result<(), SendError> {
+        // validated frame by frame as the packed messages contain many frames
+        for frame in frames {
+            self.validate_frame(&frame.0)?;
+        }
+
+        let lines: Vec<&str> = frames
+            .iter()
+            .flat_map(|frame| frame.0.iter().map(String::as_str))
+            .collect();
+
+        for message_lines in lines.chunks(Self::BULK_MESSAGE_LINES) {
+            let data_json = serialize(message_lines);
+            self.send_text(data_json).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the mean of every series of the [`SlidingWindow`] as a single line,
+    /// see [`SlidingWindow::means_line`].
+    ///
+    /// Nothing is sent when the window is empty.
+    pub async fn send_window_means(&self, window: &SlidingWindow) -> Result<(), SendError> {
+        match window.means_line() {
+            Some(line) => self.send_lines(&[line]).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn send_lines<I>(&self, lines: I) -> Result<(), SendError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let data_json = self.data_json(lines)?;
+
+        self.send_text(data_json).await
+    }
+
+    /// Serializes the lines of a Data lines message straight into a JSON array of strings,
+    /// without collecting them, and validates them when [`Client::set_frame_validation`]
+    /// is enabled.
+    ///
+    /// Every data message, including the ones of the adapters, is serialized with it
+    /// (or validated with [`Client::validate_frame`]) before it's sent.
+    pub(crate) fn data_json<I>(&self, lines: I) -> Result<String, InconsistentColumns>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        /// Serializes a line as a JSON string.
+        struct Line<S>(S);
+
+        impl<S: AsRef<str>> Serialize for Line<S> {
+            fn serialize<Ser: serde::Serializer>(
+                &self,
+                serializer: Ser,
+            ) -> Result<Ser::Ok, Ser::Error> {
+                serializer.serialize_str(self.0.as_ref())
+            }
+        }
+
+        let validate = self.inner.validate_frames.load(Ordering::Acquire);
+        // the lines can only be iterated once, so the fields are counted while serializing
+        let mut field_counts = Vec::new();
+        let lines = lines.into_iter().inspect(|line| {
+            if validate {
+                field_counts.push(field_count(line.as_ref()));
+            }
+        });
+
+        let mut data_json = Vec::new();
+        serde::Serializer::collect_seq(
+            &mut serde_json::Serializer::new(&mut data_json),
+            lines.map(Line),
+        )
+        .expect("Should always be serializable!");
+
+        if validate {
+            validate_field_counts(field_counts)?;
+        }
+
+        Ok(String::from_utf8(data_json).expect("JSON should always be UTF-8"))
+    }
+
+    /// Validates a frame which has been serialized without [`Client::data_json`]
+    /// when [`Client::set_frame_validation`] is enabled.
+    pub(crate) fn validate_frame<S: AsRef<str>>(
+        &self,
+        lines: &[S],
+    ) -> Result<(), InconsistentColumns> {
+        if self.inner.validate_frames.load(Ordering::Acquire) {
+            validate_consistent(lines)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a Data lines message to the Arduino Serial Plotter UI to plot
+    /// after verifying that every frame matches the `schema`.
+    ///
+    /// The frames are reordered to the schema's labels order, see [`DataSchema::conform`].
+    /// When any of the frames does not match, none of them are sent.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{protocol::DataSchema, Client, SchemaError};
+    ///
+    /// async fn plot(client: Client) -> Result<(), SchemaError> {
+    ///     let schema = DataSchema::new(["temp", "humidity", "pressure"]).unwrap();
+    ///
+    ///     client
+    ///         .send_schema_checked(&schema, &["humidity:40,temp:21.5,pressure:1013\n"])
+    ///         .await
+    /// }
+    /// ```
+    pub async fn send_schema_checked(
+        &self,
+        schema: &DataSchema,
+        lines: &[&str],
+    ) -> Result<(), SchemaError> {
+        let lines = lines
+            .iter()
+            .map(|line| schema.conform(line))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self.send_lines(&lines).await?)
+    }
+
+    /// Closes the websocket connection by sending a Close frame with a normal closure code.
+    ///
+    /// The [`Server`] stream of the connection ends once the Arduino Serial Plotter UI
+    /// responds with its own Close frame.
+    /// Closing an already closed connection is not an error.
+    pub async fn close(&self) -> Result<(), SendError> {
+        self.close_with(Some(CloseCode::NORMAL_CLOSURE), "").await
+    }
+
+    /// Closes the websocket connection same as [`Client::close`] but with the given
+    /// close `code` and `reason`, e.g. [`CloseCode::GOING_AWAY`] when the process is interrupted.
+    ///
+    /// The `reason` is only sent with a `code` and it's truncated to the 123 bytes
+    /// allowed in a Close frame.
+    ///
+    /// ```
+    /// use arduino_plotter::{Client, CloseCode, Server, ServerEvent};
+    /// use futures_util::StreamExt;
+    /// use tokio_websockets::{ClientBuilder, ServerBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(ui_io).split();
+    ///     let mut ui = Server::from_stream(ws_stream).events();
+    ///     let (client, _server) = Client::from_transport(ClientBuilder::new().take_over(plotter_io));
+    ///
+    ///     client
+    ///         .close_with(Some(CloseCode::GOING_AWAY), "Interrupted")
+    ///         .await
+    ///         .unwrap();
+    ///     // already closed
+    ///     client.close().await.unwrap();
+    ///
+    ///     assert_eq!(
+    ///         ServerEvent::Close {
+    ///             code: CloseCode::GOING_AWAY,
+    ///             reason: "Interrupted".to_string()
+    ///         },
+    ///         ui.next().await.unwrap().unwrap()
+    ///     );
+    /// }
+    /// ```
+    pub async fn close_with(&self, code: Option<CloseCode>, reason: &str) -> Result<(), SendError> {
+        // the Close frame payload is at most 125 bytes, 2 of which are the code
+        let mut reason_len = reason.len().min(123);
+        while !reason.is_char_boundary(reason_len) {
+            reason_len -= 1;
+        }
+        let close = Message::close(code, &reason[..reason_len]);
+
+        let result = match self.send_control(close).await {
+            Err(SendError::ClientClosed) => Ok(()),
+            result => result,
+        };
+        self.inner.closed.store(true, Ordering::Release);
+        self.inner.resumed.notify_waiters();
+
+        result
+    }
+
+    /// Send an already encoded payload to the Arduino Serial Plotter UI without
+    /// re-serializing or copying it, e.g. when proxying messages between websockets.
+    ///
+    /// The caller is responsible for the validity of the payload:
+    /// - [`FrameType::Text`] payloads must be valid UTF-8
+    /// - the payload should be a message the Arduino Serial Plotter UI understands,
+    ///   i.e. a JSON Data lines message or a [`MiddlewareCommand`]
+    ///
+    /// The payload is sent as a data message, see the [`Client`]'s ordering.
+    pub async fn send_bytes(&self, payload: Bytes, frame_type: FrameType) -> Result<(), SendError> {
+        let message = match frame_type {
+            FrameType::Text => Message::text(payload),
+            FrameType::Binary => Message::binary(payload),
+        };
+
+        self.send_data_message(message).await
+    }
+
+    /// Try to send a Data lines message to the Arduino Serial Plotter UI without waiting,
+    /// surfacing the websocket backpressure to the caller.
+    ///
+    /// `tokio-websockets` queues the outgoing frames and reports that it's ready for
+    /// a new message while less than 8096 bytes are queued.
+    /// Once the limit is reached, it's ready only after the queued frames have been
+    /// written to the connection, i.e. when the UI is not reading fast enough
+    /// the connection buffers fill up and the websocket stays not ready.
+    ///
+    /// Returns [`TrySendError::NotReady`] when the websocket is not ready,
+    /// when another message is currently being sent, when a control message
+    /// is waiting to be sent (see the [`Client`]'s ordering) or when the [`Client`] is paused.
+    ///
+    /// The accepted message is written to the connection as much as possible without waiting,
+    /// the rest is written by the next send or by [`Client::flush`].
+    ///
+    /// Any lines accepted by [`Client::send`] can be sent, pass them by reference
+    /// (e.g. a `&Vec<String>`) in order to keep them for a retry after [`TrySendError::NotReady`].
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{Client, TrySendError};
+    ///
+    /// fn plot(client: &Client, line: &str) {
+    ///     match client.try_send(&[line]) {
+    ///         Ok(()) => {}
+    ///         // the UI can't keep up, drop the frame to stay real-time
+    ///         Err(TrySendError::NotReady) => println!("Frame dropped"),
+    ///         Err(TrySendError::Send(err)) => println!("Sending failed: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn try_send<I>(&self, data: I) -> Result<(), TrySendError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        if self.is_paused() || self.inner.pending_control.load(Ordering::Acquire) > 0 {
+            return Err(TrySendError::NotReady);
+        }
+
+        let mut ws_sink = self
+            .inner
+            .ws_sink
+            .try_lock()
+            .map_err(|_| TrySendError::NotReady)?;
+        self.ensure_open()?;
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        match ws_sink.poll_ready_unpin(&mut cx) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(err)) => return Err(self.failed(err).into()),
+            Poll::Pending => return Err(TrySendError::NotReady),
+        }
+
+        let data_json = self.data_json(data).map_err(SendError::from)?;
+        ws_sink
+            .start_send_unpin(Message::text(data_json))
+            .map_err(|err| self.failed(err))?;
+
+        match ws_sink.poll_flush_unpin(&mut cx) {
+            Poll::Ready(Err(err)) => Err(self.failed(err).into()),
+            // the rest of the message is written by the next send or flush
+            Poll::Ready(Ok(())) | Poll::Pending => Ok(()),
+        }
+    }
+
+    /// Pauses sending data messages, e.g. for a pause control of a dashboard,
+    /// without disconnecting from the Arduino Serial Plotter UI.
+    ///
+    /// While paused, data messages wait until the [`Client`] is resumed
+    /// and [`Client::try_send`] returns [`TrySendError::NotReady`], i.e. the caller chooses
+    /// whether to buffer or drop the data, see also [`QueuedClient`] with its [`OverflowPolicy`].
+    /// Settings and closing are still sent while paused.
+    ///
+    /// Pausing applies to all the clones of the [`Client`].
+    ///
+    /// [`QueuedClient`]: crate::QueuedClient
+    /// [`OverflowPolicy`]: crate::OverflowPolicy
+    pub fn pause(&self) {
+        self.inner.paused.store(true, Ordering::Release);
+        debug!("Client paused");
+    }
+
+    /// Resumes sending data messages after [`Client::pause`].
+    pub fn resume(&self) {
+        self.inner.paused.store(false, Ordering::Release);
+        self.inner.resumed.notify_waiters();
+        debug!("Client resumed");
+    }
+
+    /// Enables (or disables) the frames validation debug mode.
+    ///
+    /// In this mode every data message sent with [`Client::send`], [`Client::send_strings`],
+    /// [`Client::send_owned`], [`Client::try_send`], etc. and with the adapters of the [`Client`],
+    /// e.g. [`QueuedClient`] or [`Broadcaster`], is validated with [`Data::validate_consistent`]
+    /// and an inconsistent frame is not sent but [`SendError::InconsistentColumns`]
+    /// (or the adapter's error) is returned instead.
+    /// It's disabled by default.
+    ///
+    /// [`QueuedClient`]: crate::QueuedClient
+    /// [`Broadcaster`]: crate::Broadcaster
+    pub fn set_frame_validation(&self, enabled: bool) {
+        self.inner.validate_frames.store(enabled, Ordering::Release);
+    }
+
+    /// Whether the connection has been closed with [`Client::close`] or a send has failed
+    /// with [`SendError::ClientClosed`].
+    ///
+    /// A connection closed by the Arduino Serial Plotter UI is detected only once
+    /// sending fails, see also the [`Server`] stream which ends when the UI closes the connection.
+    ///
+    /// Once closed, all the sends fail fast with [`SendError::ClientClosed`] without
+    /// touching the websocket, e.g. after an IO error which might have left a frame
+    /// partially written.
+    ///
+    /// ```
+    /// use std::{
+    ///     pin::Pin,
+    ///     sync::{
+    ///         atomic::{AtomicUsize, Ordering},
+    ///         Arc,
+    ///     },
+    ///     task::{Context, Poll},
+    /// };
+    ///
+    /// use arduino_plotter::{Client, SendError};
+    /// use futures_util::Sink;
+    /// use tokio_websockets::{Error, Message};
+    ///
+    /// /// Fails with an IO error after the message has been accepted, i.e. mid-send.
+    /// #[derive(Debug, Default)]
+    /// struct FaultySink {
+    ///     sent: Arc<AtomicUsize>,
+    /// }
+    ///
+    /// impl Sink<Message> for FaultySink {
+    ///     type Error = Error;
+    ///
+    ///     fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+    ///         Poll::Ready(Ok(()))
+    ///     }
+    ///
+    ///     fn start_send(self: Pin<&mut Self>, _: Message) -> Result<(), Error> {
+    ///         self.sent.fetch_add(1, Ordering::Relaxed);
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+    ///         let broken = std::io::Error::from(std::io::ErrorKind::BrokenPipe);
+    ///         Poll::Ready(Err(Error::Io(broken)))
+    ///     }
+    ///
+    ///     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+    ///         self.poll_flush(cx)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let sink = FaultySink::default();
+    /// let sent = sink.sent.clone();
+    /// let client = Client::from_sink(sink);
+    ///
+    /// let err = client.send(&["L1:1\n"]).await.unwrap_err();
+    /// assert!(matches!(err, SendError::ClientClosed));
+    /// assert!(client.is_closed());
+    ///
+    /// // fails fast without writing to the websocket anymore
+    /// let err = client.send(&["L1:2\n"]).await.unwrap_err();
+    /// assert!(matches!(err, SendError::ClientClosed));
+    /// assert_eq!(1, sent.load(Ordering::Relaxed));
+    /// # }
+    /// ```
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::Acquire)
+    }
+
+    /// Whether sending data messages is paused, see [`Client::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::Acquire)
+    }
+
+    /// Sends [`Data`] frames as a [`Sink`](futures_util::Sink), see [`ClientSink`].
+    pub fn into_sink(self) -> ClientSink {
+        ClientSink::new(self)
+    }
+
+    /// The number of [`Client`] handles (clones) sharing the connection, including this one.
+    ///
+    /// Useful for detecting leaked handles which keep the connection alive.
+    /// Note that wrappers may hold more than one handle, e.g. the [`QueuedClient`]
+    /// background task holds its own.
+    ///
+    /// [`QueuedClient`]: crate::QueuedClient
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// Writes all the messages accepted by [`Client::try_send`] to the connection.
+    pub async fn flush(&self) -> Result<(), SendError> {
+        let mut ws_sink = self.inner.ws_sink.lock().await;
+        self.ensure_open()?;
+
+        ws_sink.flush().await.map_err(|err| self.failed(err))
+    }
+
+    /// Sends the pending [`Client::set_settings_debounced`] settings right away and
+    /// writes all the messages accepted by [`Client::try_send`] to the connection.
+    ///
+    /// Returns once everything has been sent or on the first error.
+    /// Unlike [`Client::close`], the connection stays open.
+    pub async fn drain(&self) -> Result<(), SendError> {
+        let pending = self.inner.lock_debounced().pending.take();
+        if let Some(pending) = pending {
+            trace!("Sending the pending debounced settings");
+            self.send_settings(pending).await?;
+        }
+
+        self.flush().await
+    }
+
+    /// Sends an already serialized JSON data message.
+    pub(crate) async fn send_text(&self, json: String) -> Result<(), SendError> {
+        self.send_data_message(Message::text(json)).await
+    }
+
+    /// Sends the already serialized JSON of the `monitor_settings`,
+    /// refusing a port change like [`Client::set_monitor_settings`].
+    pub(crate) async fn send_settings_text(
+        &self,
+        monitor_settings: &MonitorSettings,
+        json: String,
+    ) -> Result<(), SendError> {
+        self.check_ws_port(monitor_settings)?;
+
+        self.send_control(Message::text(json)).await
+    }
+
+    async fn send_data_message(&self, message: Message) -> Result<(), SendError> {
+        loop {
+            let resumed = self.inner.resumed.notified();
+            tokio::pin!(resumed);
+            // register before checking in order to not miss a resume
+            resumed.as_mut().enable();
+
+            self.ensure_open()?;
+            if self.is_paused() {
+                trace!("Client is paused, waiting to be resumed");
+                resumed.await;
+                continue;
+            }
+
+            let control_sent = self.inner.control_sent.notified();
+            tokio::pin!(control_sent);
+            // register before checking in order to not miss a sent control message
+            control_sent.as_mut().enable();
+
+            if self.inner.pending_control.load(Ordering::Acquire) > 0 {
+                control_sent.await;
+                continue;
+            }
+
+            let mut ws_sink = self.inner.ws_sink.lock().await;
+            // the client might have been paused or a control message might have been
+            // requested while waiting for the lock
+            if self.is_paused() || self.inner.pending_control.load(Ordering::Acquire) > 0 {
+                continue;
+            }
+            self.ensure_open()?;
+
+            return ws_sink.send(message).await.map_err(|err| self.failed(err));
+        }
+    }
+
+    /// Sends a control message, see the [`Client`]'s ordering.
+    async fn send_control(&self, message: Message) -> Result<(), SendError> {
+        let _pending = PendingControl::new(&self.inner);
+
+        let mut ws_sink = self.inner.ws_sink.lock().await;
+        self.ensure_open()?;
+
+        ws_sink.send(message).await.map_err(|err| self.failed(err))
+    }
+
+    /// Fails fast once the connection has been closed, without touching the websocket.
+    fn ensure_open(&self) -> Result<(), SendError> {
+        if self.is_closed() {
+            return Err(SendError::ClientClosed);
+        }
+
+        Ok(())
+    }
+
+    /// Marks the connection as closed after a send failed with [`SendError::ClientClosed`],
+    /// i.e. an IO error or an already closed websocket, as the websocket can't be used anymore,
+    /// e.g. a partially written frame would corrupt the following ones.
+    ///
+    /// The other websocket errors ([`SendError::Ws`]) reject the message before it's written,
+    /// so the connection stays open and the caller learns about a closed connection
+    /// from the returned error right away.
+    fn failed(&self, err: Error) -> SendError {
+        let err = SendError::from(err);
+
+        if matches!(err, SendError::ClientClosed) {
+            self.inner.closed.store(true, Ordering::Release);
+            // wake up the paused data messages to fail as well
+            self.inner.resumed.notify_waiters();
+        }
+
+        err
     }
 }
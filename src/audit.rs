@@ -0,0 +1,210 @@
+use std::{
+    io::Write,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use crate::{
+    api::serialize,
+    protocol::{InconsistentColumns, MiddlewareCommand, MonitorSettings},
+    Client, SendError,
+};
+
+/// Errors when sending a frame with [`AuditClient`].
+#[derive(Debug, Error)]
+pub enum AuditError {
+    /// Recording the frame failed, the frame has not been sent.
+    #[error("Failed to record the frame: {0}")]
+    Io(#[from] std::io::Error),
+    /// Sending the frame failed after it has been recorded,
+    /// the record is followed by a failure record.
+    #[error(transparent)]
+    Send(#[from] SendError),
+    /// The data frame is inconsistent, it has neither been recorded nor sent,
+    /// see [`Client::set_frame_validation`].
+    #[error(transparent)]
+    InconsistentColumns(#[from] InconsistentColumns),
+}
+
+/// A [`Client`] which records every frame sent to the Arduino Serial Plotter UI
+/// into an append-only audit trail.
+///
+/// Every frame is recorded before it's sent, on a line of its own:
+///
+/// ```text
+/// <seconds since UNIX epoch>.<nanoseconds> <frame JSON>
+/// ```
+///
+/// e.g. `1718000000.000000001 ["L1:1,L2:2\n"]`.
+/// When sending the frame fails, its record is followed by a failure record
+/// with the error, i.e. [`AuditError::Send`]:
+///
+/// ```text
+/// <seconds since UNIX epoch>.<nanoseconds> FAILED <error>
+/// ```
+///
+/// The sink is flushed after every record and a frame which can't be recorded
+/// is not sent, i.e. [`AuditError::Io`].
+/// Frames are recorded in the order they are sent, as sending through the
+/// [`AuditClient`] clones happens one frame at a time.
+///
+/// Cheap to clone as it has an internal Atomic reference counter ([`Arc`]) for the sink.
+///
+/// ```no_run
+/// use std::fs::OpenOptions;
+///
+/// use arduino_plotter::{AuditClient, AuditError, Client};
+///
+/// async fn plot(client: Client) -> Result<(), AuditError> {
+///     let trail = OpenOptions::new()
+///         .create(true)
+///         .append(true)
+///         .open("plotter-audit.log")?;
+///     let audit = AuditClient::new(client, trail);
+///
+///     audit.send(&["L1:1,L2:2\n"]).await
+/// }
+/// ```
+///
+/// A frame which couldn't be sent is marked as failed:
+///
+/// ```
+/// use std::{
+///     io::Write,
+///     sync::{Arc, Mutex},
+/// };
+///
+/// use arduino_plotter::{AuditClient, AuditError, Client};
+/// use futures_util::StreamExt;
+/// use tokio_websockets::{ClientBuilder, ServerBuilder};
+///
+/// /// A trail which can be read while it's written by the [`AuditClient`].
+/// #[derive(Clone, Default)]
+/// struct Trail(Arc<Mutex<Vec<u8>>>);
+///
+/// impl Write for Trail {
+///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///
+///     fn flush(&mut self) -> std::io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let _ui = ClientBuilder::new().take_over(ui_io);
+///     let (ws_sink, _ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+///
+///     let client = Client::new(ws_sink);
+///     client.close().await.unwrap();
+///
+///     let trail = Trail::default();
+///     let audit = AuditClient::new(client, trail.clone());
+///     assert!(matches!(
+///         audit.send(&["L1:1\n"]).await,
+///         Err(AuditError::Send(_))
+///     ));
+///
+///     let records = String::from_utf8(trail.0.lock().unwrap().clone()).unwrap();
+///     let records: Vec<&str> = records
+///         .lines()
+///         .map(|record| record.split_once(' ').unwrap().1)
+///         .collect();
+///     assert_eq!(
+///         vec![
+///             r#"["L1:1\n"]"#,
+///             "FAILED The connection to the Arduino Serial Plotter UI has been closed"
+///         ],
+///         records
+///     );
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AuditClient<W> {
+    client: Client,
+    sink: Arc<Mutex<W>>,
+}
+
+impl<W> Clone for AuditClient<W> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+impl<W: Write> AuditClient<W> {
+    pub fn new(client: Client, sink: W) -> Self {
+        Self {
+            client,
+            sink: Arc::new(Mutex::new(sink)),
+        }
+    }
+
+    /// Record and send a Data lines message, see [`Client::send`].
+    pub async fn send(&self, data: &[&str]) -> Result<(), AuditError> {
+        self.send_frame(self.client.data_json(data)?, None).await
+    }
+
+    /// Record and send a Data lines message of owned [`String`]s, see [`Client::send_strings`].
+    pub async fn send_strings(&self, lines: &[String]) -> Result<(), AuditError> {
+        self.send_frame(self.client.data_json(lines)?, None).await
+    }
+
+    /// Record and send a [`MonitorSettings`], see [`Client::set_monitor_settings`].
+    pub async fn set_monitor_settings(
+        &self,
+        monitor_settings: MonitorSettings,
+    ) -> Result<(), AuditError> {
+        let json = serialize(&MiddlewareCommand(monitor_settings.clone()));
+
+        self.send_frame(json, Some(&monitor_settings)).await
+    }
+
+    async fn send_frame(
+        &self,
+        json: String,
+        settings: Option<&MonitorSettings>,
+    ) -> Result<(), AuditError> {
+        // held until the frame has been sent in order to keep the records ordered
+        let mut sink = self.sink.lock().await;
+
+        record(&mut *sink, &json)?;
+
+        let sent = match settings {
+            Some(monitor_settings) => self.client.send_settings_text(monitor_settings, json).await,
+            None => self.client.send_text(json).await,
+        };
+
+        if let Err(err) = sent {
+            // the frame is in the trail, so it's marked as not sent
+            record(&mut *sink, &format!("FAILED {err}"))?;
+
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a timestamped record and flushes the sink.
+fn record<W: Write>(sink: &mut W, record: &str) -> std::io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    writeln!(
+        sink,
+        "{}.{:09} {record}",
+        timestamp.as_secs(),
+        timestamp.subsec_nanos()
+    )?;
+
+    sink.flush()
+}
@@ -0,0 +1,129 @@
+use tokio::sync::Mutex;
+
+use crate::log::trace;
+use crate::{protocol::Data, Client, SendError};
+
+/// A [`Client`] which buffers the [`Data`] frames and sends them packed into
+/// a single Data lines message, reducing the per-message overhead of high-rate plotting.
+///
+/// The buffer is sent when it reaches `max_lines` lines or on [`BatchedClient::flush`],
+/// e.g. from an interval which bounds the latency of the plot.
+/// The recommended maximum is [`Client::BULK_MESSAGE_LINES`], larger messages
+/// don't reduce the overhead any further while they delay the plot.
+///
+/// The buffered frames are not sent when the [`BatchedClient`] is dropped,
+/// [`BatchedClient::flush`] it first.
+///
+/// ```no_run
+/// use core::time::Duration;
+/// use std::sync::Arc;
+///
+/// use arduino_plotter::{protocol::Data, BatchedClient, Client, SendError};
+///
+/// async fn plot(client: Client) -> Result<(), SendError> {
+///     let batched = Arc::new(BatchedClient::new(client, 100));
+///
+///     // bound the latency of the plot for the slower frames
+///     let flusher = tokio::spawn({
+///         let batched = batched.clone();
+///         async move {
+///             let mut interval = tokio::time::interval(Duration::from_millis(50));
+///             loop {
+///                 interval.tick().await;
+///                 if batched.flush().await.is_err() {
+///                     break;
+///                 }
+///             }
+///         }
+///     });
+///
+///     for i in 0..10_000 {
+///         batched.push(Data(vec![format!("L1:{i}\n")])).await?;
+///     }
+///
+///     flusher.abort();
+///     batched.flush().await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BatchedClient {
+    client: Client,
+    max_lines: usize,
+    buffer: Mutex<Buffer>,
+}
+
+#[derive(Debug, Default)]
+struct Buffer {
+    frames: Vec<Data<String>>,
+    lines: usize,
+}
+
+impl BatchedClient {
+    /// Creates a new [`BatchedClient`] sending the buffered frames once they
+    /// have `max_lines` lines.
+    ///
+    /// # Panics
+    ///
+    /// When `max_lines` is `0`.
+    pub fn new(client: Client, max_lines: usize) -> Self {
+        assert!(max_lines > 0, "Batch max lines should be greater than 0");
+
+        Self {
+            client,
+            max_lines,
+            buffer: Mutex::new(Buffer::default()),
+        }
+    }
+
+    /// Buffers the frame and sends the buffer once it has reached the max lines.
+    ///
+    /// When sending fails, the buffered frames are discarded.
+    pub async fn push(&self, frame: Data<String>) -> Result<(), SendError> {
+        let mut buffer = self.buffer.lock().await;
+        buffer.lines += frame.0.len();
+        buffer.frames.push(frame);
+
+        if buffer.lines < self.max_lines {
+            return Ok(());
+        }
+
+        trace!(lines = buffer.lines, "Max lines reached, sending the batch");
+        self.send(&mut buffer).await
+    }
+
+    /// Sends all the buffered frames, see [`Client::send_bulk`].
+    ///
+    /// When sending fails, the buffered frames are discarded.
+    pub async fn flush(&self) -> Result<(), SendError> {
+        let mut buffer = self.buffer.lock().await;
+
+        self.send(&mut buffer).await
+    }
+
+    /// The number of buffered lines.
+    pub async fn len(&self) -> usize {
+        self.buffer.lock().await.lines
+    }
+
+    /// Whether there are no buffered lines.
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Returns the underlying [`Client`] discarding the buffered frames.
+    pub fn into_inner(self) -> Client {
+        self.client
+    }
+
+    async fn send(&self, buffer: &mut Buffer) -> Result<(), SendError> {
+        let frames = core::mem::take(&mut buffer.frames);
+        buffer.lines = 0;
+
+        if frames.is_empty() {
+            return Ok(());
+        }
+
+        // the lock is held while sending in order to keep the frames ordered
+        self.client.send_bulk(&frames).await
+    }
+}
@@ -0,0 +1,251 @@
+use core::time::Duration;
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::TcpStream,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::Uri;
+use thiserror::Error;
+
+use crate::{
+    api::{port_change, serialize},
+    protocol::{MiddlewareCommand, MonitorSettings},
+};
+
+/// The GUID appended to the `Sec-WebSocket-Key` for the `Sec-WebSocket-Accept` (RFC 6455).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The maximum size of the handshake response headers.
+const MAX_RESPONSE_LEN: usize = 8 * 1024;
+
+/// How long [`BlockingClient::close`] waits for the connection to be closed.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+
+/// Errors of the [`BlockingClient`].
+#[derive(Debug, Error)]
+pub enum BlockingError {
+    /// An IO error occurred on the connection.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Only `ws://` URIs are supported.
+    #[error("Only the ws:// scheme is supported")]
+    UnsupportedScheme,
+    /// The URI has no host.
+    #[error("The URI has no host")]
+    CannotResolveHost,
+    /// The server did not accept the websocket handshake.
+    #[error("Websocket handshake failed: {0}")]
+    Handshake(String),
+    /// The settings change the websocket port, which makes the UI close the current connection,
+    /// nothing has been sent.
+    ///
+    /// See [`SendError::PortChangeWouldDisconnect`](crate::SendError::PortChangeWouldDisconnect).
+    #[error("Changing the websocket port to {ws_port} would disconnect the UI")]
+    PortChangeWouldDisconnect { ws_port: u16 },
+}
+
+/// A synchronous [`Client`] for simple scripts which only push a few frames
+/// and don't want to run a tokio runtime.
+///
+/// It uses a minimal blocking websocket implementation which only sends messages,
+/// any messages received from the Arduino Serial Plotter UI are ignored.
+///
+/// Available with the `blocking` feature.
+///
+/// ```no_run
+/// use arduino_plotter::{protocol::MonitorSettings, BlockingClient, BlockingError};
+/// use http::Uri;
+///
+/// fn main() -> Result<(), BlockingError> {
+///     let mut client = BlockingClient::connect(Uri::from_static("ws://127.0.0.1:3030"))?;
+///
+///     client.set_monitor_settings(MonitorSettings::default().into_full())?;
+///     for i in 0..10 {
+///         client.send(&[&format!("L1:{i}\n")])?;
+///     }
+///
+///     client.close()
+/// }
+/// ```
+///
+/// [`Client`]: crate::Client
+#[derive(Debug)]
+pub struct BlockingClient {
+    stream: TcpStream,
+    /// The port of the `uri` the client is connected to.
+    ws_port: u16,
+}
+
+impl BlockingClient {
+    /// Connects to a websocket at the `ws://` `uri` and performs the websocket handshake.
+    pub fn connect(uri: Uri) -> Result<Self, BlockingError> {
+        if uri.scheme_str() != Some("ws") {
+            return Err(BlockingError::UnsupportedScheme);
+        }
+        let authority = uri.authority().ok_or(BlockingError::CannotResolveHost)?;
+        let port = authority.port_u16().unwrap_or(80);
+        // IPv6 hosts are enclosed in brackets
+        let host = authority
+            .host()
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+
+        let mut stream = TcpStream::connect((host, port))?;
+        stream.set_nodelay(true)?;
+
+        let key = STANDARD.encode(rand::random::<[u8; 16]>());
+        let path = uri.path_and_query().map_or("/", |path| path.as_str());
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\n\
+             Host: {authority}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {key}\r\n\
+             Sec-WebSocket-Version: 13\r\n\
+             \r\n"
+        )?;
+
+        let response = read_response(&mut stream)?;
+        verify_response(&response, &key)?;
+
+        Ok(Self {
+            stream,
+            ws_port: port,
+        })
+    }
+
+    /// Send a Data lines message to the Arduino Serial Plotter UI to plot.
+    pub fn send(&mut self, data: &[&str]) -> Result<(), BlockingError> {
+        self.send_text(&serialize(data))
+    }
+
+    /// Send a Data lines message of owned [`String`]s to the Arduino Serial Plotter UI to plot.
+    pub fn send_strings(&mut self, lines: &[String]) -> Result<(), BlockingError> {
+        self.send_text(&serialize(lines))
+    }
+
+    /// Send a [`MonitorSettings`] ([`MiddlewareCommand`]) to the Arduino Serial Plotter UI.
+    ///
+    /// Like [`Client::set_monitor_settings`], settings with a
+    /// [`MonitorModelState::ws_port`] other than the port of the connection are not sent.
+    ///
+    /// [`Client::set_monitor_settings`]: crate::Client::set_monitor_settings
+    /// [`MonitorModelState::ws_port`]: crate::protocol::MonitorModelState::ws_port
+    pub fn set_monitor_settings(
+        &mut self,
+        monitor_settings: MonitorSettings,
+    ) -> Result<(), BlockingError> {
+        if let Some(ws_port) = port_change(Some(self.ws_port), &monitor_settings) {
+            return Err(BlockingError::PortChangeWouldDisconnect { ws_port });
+        }
+
+        self.send_text(&serialize(&MiddlewareCommand(monitor_settings)))
+    }
+
+    /// Closes the websocket connection by sending a Close frame with a normal closure code.
+    ///
+    /// Waits up to 1 second for the Arduino Serial Plotter UI to respond and close the connection.
+    pub fn close(mut self) -> Result<(), BlockingError> {
+        // 1000 - normal closure
+        self.write_frame(OPCODE_CLOSE, &1000_u16.to_be_bytes())?;
+
+        // read until the connection is closed, as closing it with unread
+        // received data resets the connection instead
+        self.stream.set_read_timeout(Some(CLOSE_TIMEOUT))?;
+        match std::io::copy(&mut self.stream, &mut std::io::sink()) {
+            Ok(_) => Ok(()),
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn send_text(&mut self, json: &str) -> Result<(), BlockingError> {
+        self.write_frame(OPCODE_TEXT, json.as_bytes())
+    }
+
+    /// Writes a single masked frame, as required for frames sent by clients.
+    fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), BlockingError> {
+        const FIN: u8 = 0x80;
+        const MASK: u8 = 0x80;
+
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(FIN | opcode);
+        match payload.len() {
+            len @ 0..=125 => frame.push(MASK | len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(MASK | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(MASK | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+
+        let mask = rand::random::<[u8; 4]>();
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .zip(mask.iter().cycle())
+                .map(|(byte, mask)| byte ^ mask),
+        );
+
+        self.stream.write_all(&frame)?;
+
+        Ok(())
+    }
+}
+
+/// Reads the handshake response headers without reading any of the following frames.
+fn read_response(stream: &mut TcpStream) -> Result<String, BlockingError> {
+    let mut response = Vec::new();
+    let mut byte = [0; 1];
+
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_RESPONSE_LEN {
+            return Err(BlockingError::Handshake("Response is too long".into()));
+        }
+        if stream.read(&mut byte)? == 0 {
+            return Err(BlockingError::Handshake("Connection closed".into()));
+        }
+        response.push(byte[0]);
+    }
+
+    String::from_utf8(response)
+        .map_err(|_| BlockingError::Handshake("Response is not valid UTF-8".into()))
+}
+
+fn verify_response(response: &str, key: &str) -> Result<(), BlockingError> {
+    let mut lines = response.lines();
+
+    let status_line = lines.next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("101") {
+        return Err(BlockingError::Handshake(format!(
+            "Unexpected response status: {status_line}"
+        )));
+    }
+
+    let accept = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("sec-websocket-accept"))
+        .map(|(_, value)| value.trim());
+    let expected = STANDARD.encode(
+        sha1_smol::Sha1::from(format!("{key}{WEBSOCKET_GUID}"))
+            .digest()
+            .bytes(),
+    );
+
+    if accept != Some(expected.as_str()) {
+        return Err(BlockingError::Handshake(
+            "Invalid Sec-WebSocket-Accept header".into(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures_util::future::join_all;
+
+use crate::log::debug;
+use crate::{
+    api::serialize,
+    protocol::{MiddlewareCommand, MonitorSettings},
+    Client, SendError,
+};
+
+/// The identifier of a [`Client`] added to a [`Broadcaster`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClientId(u64);
+
+impl core::fmt::Display for ClientId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The summarized result of broadcasting a message with [`Broadcaster`].
+#[derive(Debug, Default)]
+pub struct BroadcastReport {
+    /// The number of clients the message has been sent to.
+    pub sent: usize,
+    /// The clients which failed to send the message, they have been removed from the [`Broadcaster`]
+    /// unless the message has been refused with [`SendError::PortChangeWouldDisconnect`]
+    /// or [`SendError::InconsistentColumns`], see [`Client::set_frame_validation`].
+    pub failures: Vec<(ClientId, SendError)>,
+}
+
+impl BroadcastReport {
+    /// Whether the message has been sent to all the clients.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Sends the same messages to multiple Arduino Serial Plotter UIs, e.g. in
+/// multi-viewer deployments.
+///
+/// The message is serialized once and sent to all the clients concurrently.
+/// A client which fails to send is considered dead and is removed.
+///
+/// Cheap to clone as it has an internal Atomic reference counter ([`Arc`]) for the clients.
+///
+/// ```no_run
+/// use arduino_plotter::{Broadcaster, Client};
+///
+/// async fn plot(viewers: Vec<Client>) {
+///     let broadcaster = Broadcaster::new();
+///     for viewer in viewers {
+///         broadcaster.add(viewer);
+///     }
+///
+///     let report = broadcaster.broadcast_data(&["L1:1,L2:2\n"]).await;
+///     for (id, err) in &report.failures {
+///         println!("Viewer {id} removed: {err}");
+///     }
+///     println!("Sent to {} viewers", report.sent);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Broadcaster {
+    next_id: Arc<AtomicU64>,
+    clients: Arc<Mutex<HashMap<ClientId, Client>>>,
+}
+
+impl Broadcaster {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a client to broadcast to.
+    pub fn add(&self, client: Client) -> ClientId {
+        let id = ClientId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.clients.lock().unwrap().insert(id, client);
+
+        id
+    }
+
+    /// Removes a client, returning it if it hasn't been removed already.
+    pub fn remove(&self, id: ClientId) -> Option<Client> {
+        self.clients.lock().unwrap().remove(&id)
+    }
+
+    /// The number of clients to broadcast to.
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Whether there are no clients to broadcast to.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Broadcast a Data lines message, see [`Client::send`].
+    pub async fn broadcast_data(&self, data: &[&str]) -> BroadcastReport {
+        self.broadcast(serialize(data), Broadcast::Data(data)).await
+    }
+
+    /// Broadcast a [`MonitorSettings`], see [`Client::set_monitor_settings`].
+    ///
+    /// The port change check is done for every client, as the clients might be
+    /// connected to different ports.
+    pub async fn broadcast_settings(&self, monitor_settings: MonitorSettings) -> BroadcastReport {
+        let json = serialize(&MiddlewareCommand(monitor_settings.clone()));
+
+        self.broadcast(json, Broadcast::Settings(&monitor_settings))
+            .await
+    }
+
+    async fn broadcast(&self, json: String, message: Broadcast<'_>) -> BroadcastReport {
+        let clients: Vec<(ClientId, Client)> = self
+            .clients
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, client)| (*id, client.clone()))
+            .collect();
+
+        let results = join_all(clients.into_iter().map(|(id, client)| {
+            let json = json.clone();
+
+            async move {
+                // checked for every client as their validation and port may differ
+                let result = match message {
+                    Broadcast::Data(data) => match client.validate_frame(data) {
+                        Ok(()) => client.send_text(json).await,
+                        Err(inconsistent) => Err(inconsistent.into()),
+                    },
+                    Broadcast::Settings(monitor_settings) => {
+                        client.send_settings_text(monitor_settings, json).await
+                    }
+                };
+
+                (id, result)
+            }
+        }))
+        .await;
+
+        let mut report = BroadcastReport::default();
+        for (id, result) in results {
+            match result {
+                Ok(()) => report.sent += 1,
+                // nothing has been sent, the client is still alive
+                Err(
+                    err @ (SendError::PortChangeWouldDisconnect { .. }
+                    | SendError::InconsistentColumns(_)),
+                ) => {
+                    report.failures.push((id, err));
+                }
+                Err(err) => {
+                    debug!(%id, ?err, "Broadcasting failed, removing client");
+                    self.remove(id);
+                    report.failures.push((id, err));
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// The broadcast message, for the checks done before sending it to every client.
+#[derive(Clone, Copy)]
+enum Broadcast<'a> {
+    Data(&'a [&'a str]),
+    Settings(&'a MonitorSettings),
+}
@@ -0,0 +1,147 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::Poll,
+};
+
+use futures_util::{Stream, StreamExt};
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    task::JoinHandle,
+};
+
+use crate::log::trace;
+use crate::{protocol::ClientCommand, Server, ServerError};
+
+/// A [`Server`] adapter which reads the received commands ahead into a bounded buffer.
+///
+/// The commands are read from the websocket by a spawned task, so a consumer doing heavy
+/// per-command work doesn't stall the reading of the websocket (e.g. its Close frame)
+/// until the buffer fills up. Once it's full and a received command waits for room,
+/// the websocket is no longer read, the following messages stay in the connection buffers
+/// and the TCP flow control slows down the writes of the UI.
+///
+/// Every time a received command has to wait for room, a [`ServerError::BufferFull`] is
+/// yielded before the buffered commands, i.e. the consumer can't keep up with the UI.
+/// The Arduino Serial Plotter UI only sends a command on a user action, e.g. a changed setting
+/// or a sent message, so a full buffer means the consumer has been stuck for a while,
+/// e.g. on a slow serial port.
+///
+/// Errors are always yielded and the stream ends with the [`Server`] one
+/// when the websocket is closed.
+/// Dropping the [`Buffered`] stops the reading task and discards the buffered commands.
+///
+/// ```
+/// use arduino_plotter::{protocol::ClientCommand, Server, ServerError};
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let mut ui = ClientBuilder::new().take_over(ui_io);
+///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+///     let mut commands = Server::from_stream(ws_stream).with_capacity(1);
+///
+///     for message in ["first", "second"] {
+///         let command = ClientCommand::SendMessage(message.to_string());
+///         let json = serde_json::to_string(&command).unwrap();
+///         ui.send(Message::text(json)).await.unwrap();
+///     }
+///     // let the reading task fill up the buffer
+///     tokio::time::sleep(core::time::Duration::from_millis(50)).await;
+///
+///     assert!(matches!(
+///         commands.next().await,
+///         Some(Err(ServerError::BufferFull { capacity: 1 }))
+///     ));
+///     for expected in ["first", "second"] {
+///         let command = commands.next().await.unwrap().unwrap();
+///         assert_eq!(ClientCommand::SendMessage(expected.to_string()), command);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Buffered {
+    server: Server,
+    capacity: usize,
+    receiver: mpsc::Receiver<Result<ClientCommand, ServerError>>,
+    /// Set by the reading task when a received command waits for room.
+    full: Arc<AtomicBool>,
+    reader: JoinHandle<()>,
+}
+
+impl Buffered {
+    /// # Panics
+    ///
+    /// When `capacity` is zero or when not called from a tokio runtime.
+    pub fn new(server: Server, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let full = Arc::new(AtomicBool::new(false));
+        let reader = tokio::spawn(read_ahead(server.clone(), sender, full.clone()));
+
+        Self {
+            server,
+            capacity,
+            receiver,
+            full,
+            reader,
+        }
+    }
+
+    /// Returns the underlying [`Server`], the buffered commands are discarded.
+    pub fn into_inner(self) -> Server {
+        self.reader.abort();
+
+        self.server.clone()
+    }
+}
+
+impl Drop for Buffered {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Reads the commands of the `server` into the buffer, waiting for room when it's full.
+async fn read_ahead(
+    mut server: Server,
+    sender: mpsc::Sender<Result<ClientCommand, ServerError>>,
+    full: Arc<AtomicBool>,
+) {
+    while let Some(received) = server.next().await {
+        let waiting = match sender.try_send(received) {
+            Ok(()) => continue,
+            Err(TrySendError::Full(waiting)) => waiting,
+            Err(TrySendError::Closed(_)) => break,
+        };
+
+        trace!("Receive buffer is full");
+        full.store(true, Ordering::Release);
+
+        if sender.send(waiting).await.is_err() {
+            break;
+        }
+    }
+}
+
+impl Stream for Buffered {
+    type Item = Result<ClientCommand, ServerError>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.full.swap(false, Ordering::AcqRel) {
+            return Poll::Ready(Some(Err(ServerError::BufferFull {
+                capacity: this.capacity,
+            })));
+        }
+
+        this.receiver.poll_recv(cx)
+    }
+}
@@ -0,0 +1,138 @@
+use core::time::Duration;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{FutureExt, Stream, StreamExt};
+use tokio::time::{Instant, Sleep};
+
+use crate::log::trace;
+use crate::{
+    protocol::{ClientCommand, MonitorSettings},
+    Server, ServerError,
+};
+
+/// A [`Server`] adapter which coalesces the [`ClientCommand::ChangeSettings`] commands received
+/// in quick succession, e.g. while the user drags a slider in the UI, into a single one.
+///
+/// The settings are yielded once no new settings have been received within the `window`,
+/// merged with [`MonitorSettings::merge`], i.e. the latest value of every setting is kept.
+/// [`ClientCommand::SendMessage`] commands and errors are yielded right away,
+/// before the pending settings, and the pending settings are yielded when the stream ends.
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use arduino_plotter::{
+///     protocol::{ClientCommand, MonitorSettings},
+///     Server,
+/// };
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let mut ui = ClientBuilder::new().take_over(ui_io);
+///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+///     let mut commands = Server::from_stream(ws_stream).debounced(Duration::from_millis(50));
+///
+///     let settings = |settings: MonitorSettings| {
+///         let command = ClientCommand::ChangeSettings(settings);
+///         Message::text(serde_json::to_string(&command).unwrap())
+///     };
+///     ui.send(settings(MonitorSettings::builder().dark_theme(true).build()))
+///         .await
+///         .unwrap();
+///     ui.send(settings(MonitorSettings::builder().interpolate(true).build()))
+///         .await
+///         .unwrap();
+///     ui.send(settings(MonitorSettings::builder().interpolate(false).build()))
+///         .await
+///         .unwrap();
+///     let message = r#"{"command": "SEND_MESSAGE", "data": "hello"}"#;
+///     ui.send(Message::text(message.to_string())).await.unwrap();
+///
+///     assert_eq!(
+///         ClientCommand::SendMessage("hello".to_string()),
+///         commands.next().await.unwrap().unwrap()
+///     );
+///     assert_eq!(
+///         ClientCommand::ChangeSettings(
+///             MonitorSettings::builder()
+///                 .dark_theme(true)
+///                 .interpolate(false)
+///                 .build()
+///         ),
+///         commands.next().await.unwrap().unwrap()
+///     );
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Debounced {
+    server: Server,
+    window: Duration,
+    /// The coalesced settings and the end of their window.
+    pending: Option<(MonitorSettings, Pin<Box<Sleep>>)>,
+}
+
+impl Debounced {
+    pub fn new(server: Server, window: Duration) -> Self {
+        Self {
+            server,
+            window,
+            pending: None,
+        }
+    }
+
+    /// Returns the underlying [`Server`], the pending settings are discarded.
+    pub fn into_inner(self) -> Server {
+        self.server
+    }
+
+    fn take_pending(&mut self) -> Option<ClientCommand> {
+        self.pending
+            .take()
+            .map(|(settings, _)| ClientCommand::ChangeSettings(settings))
+    }
+}
+
+impl Stream for Debounced {
+    type Item = Result<ClientCommand, ServerError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // read all the received commands before checking the window
+        loop {
+            match this.server.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(ClientCommand::ChangeSettings(settings)))) => {
+                    let deadline = Instant::now() + this.window;
+
+                    this.pending = Some(match this.pending.take() {
+                        Some((pending, mut window_end)) => {
+                            trace!("Settings coalesced with the pending ones");
+                            window_end.as_mut().reset(deadline);
+
+                            (pending.merge(settings), window_end)
+                        }
+                        None => (settings, Box::pin(tokio::time::sleep_until(deadline))),
+                    });
+                }
+                Poll::Ready(Some(other)) => return Poll::Ready(Some(other)),
+                Poll::Ready(None) => return Poll::Ready(this.take_pending().map(Ok)),
+                Poll::Pending => break,
+            }
+        }
+
+        match this.pending.as_mut() {
+            Some((_, window_end)) => {
+                futures_util::ready!(window_end.poll_unpin(cx));
+
+                Poll::Ready(this.take_pending().map(Ok))
+            }
+            None => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use core::time::Duration;
+use std::task::{ready, Poll};
+
+use futures_util::{Stream, StreamExt};
+use tokio::time::Instant;
+
+use crate::log::trace;
+use crate::{protocol::ClientCommand, Server, ServerError};
+
+/// When a [`ClientCommand`] identical to the previous one is suppressed by [`Dedup`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupWindow {
+    /// Suppress any command identical to the previous one.
+    #[default]
+    Previous,
+    /// Suppress a command identical to the previous one only when it's received
+    /// within the duration since the previous one has been yielded.
+    Within(Duration),
+}
+
+/// A [`Server`] adapter which suppresses consecutive identical [`ClientCommand`]s,
+/// e.g. the same `CHANGE_SETTINGS` sent multiple times by re-renders of the UI.
+///
+/// Errors are always yielded and don't affect the previous command and
+/// the stream ends with the [`Server`] one when the websocket is closed.
+///
+/// ```no_run
+/// use arduino_plotter::{protocol::ClientCommand, DedupWindow, Server};
+/// use futures_util::StreamExt;
+///
+/// async fn handle(server: Server) {
+///     let mut commands = server.dedup(DedupWindow::Previous);
+///
+///     while let Some(Ok(command)) = commands.next().await {
+///         if let ClientCommand::ChangeSettings(settings) = command {
+///             // reopen the serial port only once for the repeated settings
+///             println!("Settings changed: {settings:?}");
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Dedup {
+    server: Server,
+    window: DedupWindow,
+    /// The last yielded command and when it has been yielded.
+    previous: Option<(ClientCommand, Instant)>,
+}
+
+impl Dedup {
+    pub fn new(server: Server, window: DedupWindow) -> Self {
+        Self {
+            server,
+            window,
+            previous: None,
+        }
+    }
+
+    /// Returns the underlying [`Server`].
+    pub fn into_inner(self) -> Server {
+        self.server
+    }
+}
+
+impl Stream for Dedup {
+    type Item = Result<ClientCommand, ServerError>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let command = match ready!(this.server.poll_next_unpin(cx)) {
+                Some(Ok(command)) => command,
+                other => return Poll::Ready(other),
+            };
+
+            let now = Instant::now();
+            let duplicate = match (&this.previous, this.window) {
+                (Some((previous, _)), DedupWindow::Previous) => previous == &command,
+                (Some((previous, yielded_at)), DedupWindow::Within(window)) => {
+                    previous == &command && now.duration_since(*yielded_at) < window
+                }
+                (None, _) => false,
+            };
+
+            if duplicate {
+                trace!(?command, "Duplicate client command suppressed");
+                continue;
+            }
+
+            this.previous = Some((command.clone(), now));
+
+            return Poll::Ready(Some(Ok(command)));
+        }
+    }
+}
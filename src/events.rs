@@ -0,0 +1,185 @@
+use std::task::{ready, Poll};
+
+use futures_util::Stream;
+
+use crate::{
+    protocol::{unknown_command, ClientCommand},
+    CloseCode, Server, ServerError,
+};
+
+/// The Close frame of a websocket connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    /// The close code, [`CloseCode::NO_STATUS_RECEIVED`] when the frame has none.
+    pub code: CloseCode,
+    /// The close reason, empty when there's none.
+    pub reason: String,
+}
+
+impl CloseFrame {
+    /// Parses a Close frame payload, a big-endian close code followed by a UTF-8 reason.
+    pub(crate) fn parse(payload: &[u8]) -> Self {
+        let code = match payload {
+            [high, low, ..] => CloseCode::try_from(u16::from_be_bytes([*high, *low]))
+                // received codes are validated by the websocket
+                .unwrap_or(CloseCode::NO_STATUS_RECEIVED),
+            _ => CloseCode::NO_STATUS_RECEIVED,
+        };
+        let reason = payload
+            .get(2..)
+            .map(|reason| String::from_utf8_lossy(reason).into_owned())
+            .unwrap_or_default();
+
+        Self { code, reason }
+    }
+}
+
+/// An event yielded by [`ServerEvents`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerEvent {
+    /// A received [`ClientCommand`].
+    Command(ClientCommand),
+    /// A received command object with a `command` name which is not a known
+    /// [`CommandName`](crate::protocol::CommandName), e.g. sent by a newer webapp version.
+    ///
+    /// The [`Server`] stream yields it as a [`ServerError::Unrecognized`] error instead.
+    UnknownCommand {
+        /// The `command` name, e.g. `"CLEAR_PLOT"`.
+        name: String,
+        /// The `data` of the command, [`serde_json::Value::Null`] when missing.
+        data: serde_json::Value,
+    },
+    /// The UI has closed the connection, it's the last event of the stream.
+    Close { code: CloseCode, reason: String },
+    /// A Ping frame with its payload, the websocket replies to it by itself.
+    Ping(Vec<u8>),
+    /// A Pong frame with its payload, e.g. replying to the Pings of
+    /// [`Client::spawn_keepalive`](crate::Client::spawn_keepalive).
+    Pong(Vec<u8>),
+}
+
+/// A [`Server`] adapter which yields the control frames of the connection as well,
+/// i.e. the Ping and Pong frames, which the [`Server`] stream skips, and the Close frame
+/// of the UI as a [`ServerEvent::Close`] before the stream ends, so a clean close can be told
+/// from a failed connection, e.g. for deciding whether to reconnect.
+///
+/// Commands unknown to this crate version are yielded as [`ServerEvent::UnknownCommand`]
+/// instead of errors, so they are not reported to the [`Server::on_violation`] callback.
+///
+/// Errors are always yielded and the stream ends with the [`Server`] one,
+/// without a `Closed` event when the connection has failed.
+///
+/// ```
+/// use arduino_plotter::{protocol::ClientCommand, CloseCode, Server, ServerEvent};
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let mut ui = ClientBuilder::new().take_over(ui_io);
+///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+///     let mut events = Server::from_stream(ws_stream).events();
+///
+///     let message = r#"{"command": "SEND_MESSAGE", "data": "hello"}"#;
+///     ui.send(Message::text(message.to_string())).await.unwrap();
+///     let unknown = r#"{"command": "CLEAR_PLOT", "data": {"keepLabels": true}}"#;
+///     ui.send(Message::text(unknown.to_string())).await.unwrap();
+///     ui.send(Message::ping("alive?")).await.unwrap();
+///     ui.send(Message::close(Some(CloseCode::GOING_AWAY), "Tab closed"))
+///         .await
+///         .unwrap();
+///
+///     assert_eq!(
+///         ServerEvent::Command(ClientCommand::SendMessage("hello".to_string())),
+///         events.next().await.unwrap().unwrap()
+///     );
+///     assert_eq!(
+///         ServerEvent::UnknownCommand {
+///             name: "CLEAR_PLOT".to_string(),
+///             data: serde_json::json!({"keepLabels": true}),
+///         },
+///         events.next().await.unwrap().unwrap()
+///     );
+///     assert_eq!(
+///         ServerEvent::Ping(b"alive?".to_vec()),
+///         events.next().await.unwrap().unwrap()
+///     );
+///     assert_eq!(
+///         ServerEvent::Close {
+///             code: CloseCode::GOING_AWAY,
+///             reason: "Tab closed".to_string()
+///         },
+///         events.next().await.unwrap().unwrap()
+///     );
+///     assert!(events.next().await.is_none());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ServerEvents {
+    server: Server,
+    /// The `Closed` event has been yielded.
+    closed: bool,
+}
+
+impl ServerEvents {
+    pub fn new(server: Server) -> Self {
+        Self {
+            server,
+            closed: false,
+        }
+    }
+
+    /// Returns the underlying [`Server`].
+    pub fn into_inner(self) -> Server {
+        self.server
+    }
+}
+
+impl Stream for ServerEvents {
+    type Item = Result<ServerEvent, ServerError>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        let next = this.server.poll_decode(
+            cx,
+            |text_payload| match serde_json::from_str::<ClientCommand>(text_payload) {
+                Ok(command) => Ok(ServerEvent::Command(command)),
+                Err(err) => match unknown_command(text_payload) {
+                    Some((name, data)) => Ok(ServerEvent::UnknownCommand { name, data }),
+                    None => Err(ServerError::Json(err)),
+                },
+            },
+            |message| {
+                let payload = message.as_payload().to_vec();
+
+                Some(if message.is_ping() {
+                    ServerEvent::Ping(payload)
+                } else {
+                    ServerEvent::Pong(payload)
+                })
+            },
+        );
+
+        match ready!(next) {
+            Some(result) => Poll::Ready(Some(result)),
+            None if this.closed => Poll::Ready(None),
+            None => {
+                this.closed = true;
+
+                let closed = this.server.close_frame().map(|close_frame| {
+                    Ok(ServerEvent::Close {
+                        code: close_frame.code,
+                        reason: close_frame.reason,
+                    })
+                });
+
+                Poll::Ready(closed)
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::task::{ready, Poll};
+
+use futures_util::{Stream, StreamExt};
+
+use crate::log::trace;
+use crate::{protocol::ClientCommand, Server, ServerError};
+
+/// An event yielded by [`GenerateEvents`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerateEvent {
+    /// A received [`ClientCommand`].
+    Command(ClientCommand),
+    /// The [`MonitorModelState::generate`] flag has changed,
+    /// i.e. the mocked data generation should be started (`true`) or stopped (`false`).
+    ///
+    /// [`MonitorModelState::generate`]: crate::protocol::MonitorModelState::generate
+    GenerateRequested(bool),
+}
+
+/// A [`Server`] adapter which surfaces the changes of the `generate` flag of the
+/// received [`ClientCommand::ChangeSettings`] as [`GenerateEvent::GenerateRequested`].
+///
+/// Mocked data generation is initially stopped.
+/// Every `ChangeSettings` is yielded as a [`GenerateEvent::Command`] and when it has
+/// a `generate` flag which differs from the current one, it's followed by a
+/// [`GenerateEvent::GenerateRequested`] with the new flag.
+/// Partial settings without the `generate` flag keep the current one.
+///
+/// Errors are always yielded and the stream ends with the [`Server`] one
+/// when the websocket is closed.
+///
+/// ```no_run
+/// use arduino_plotter::{GenerateEvent, Server};
+/// use futures_util::StreamExt;
+///
+/// async fn handle(server: Server) {
+///     let mut events = server.generate_events();
+///
+///     while let Some(Ok(event)) = events.next().await {
+///         match event {
+///             GenerateEvent::GenerateRequested(true) => println!("Start the mock generator"),
+///             GenerateEvent::GenerateRequested(false) => println!("Stop the mock generator"),
+///             GenerateEvent::Command(command) => println!("Received: {command:?}"),
+///         }
+///     }
+/// }
+/// ```
+///
+/// A partial settings update, e.g. of the theme, doesn't stop the generation:
+///
+/// ```
+/// use arduino_plotter::{
+///     protocol::{ClientCommand, MonitorSettings},
+///     GenerateEvent, Server,
+/// };
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let mut ui = ClientBuilder::new().take_over(ui_io);
+///     let (_ws_sink, ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+///     let mut events = Server::from_stream(ws_stream).generate_events();
+///
+///     let generate = ClientCommand::ChangeSettings(MonitorSettings::builder().generate(true).build());
+///     let dark_theme =
+///         ClientCommand::ChangeSettings(MonitorSettings::builder().dark_theme(true).build());
+///     let hello = ClientCommand::SendMessage("hello".to_string());
+///     for command in [&generate, &dark_theme, &hello] {
+///         let json = serde_json::to_string(command).unwrap();
+///         ui.send(Message::text(json)).await.unwrap();
+///     }
+///
+///     let received = events.by_ref().take(4).map(Result::unwrap).collect::<Vec<_>>().await;
+///     assert_eq!(
+///         vec![
+///             GenerateEvent::Command(generate),
+///             GenerateEvent::GenerateRequested(true),
+///             GenerateEvent::Command(dark_theme),
+///             GenerateEvent::Command(hello),
+///         ],
+///         received
+///     );
+///     assert!(events.is_generate_requested());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct GenerateEvents {
+    server: Server,
+    /// The current `generate` flag.
+    generate: bool,
+    /// A changed `generate` flag to be yielded after its command.
+    changed: Option<bool>,
+}
+
+impl GenerateEvents {
+    pub fn new(server: Server) -> Self {
+        Self {
+            server,
+            generate: false,
+            changed: None,
+        }
+    }
+
+    /// Whether mocked data generation is currently requested.
+    pub fn is_generate_requested(&self) -> bool {
+        self.generate
+    }
+
+    /// Returns the underlying [`Server`].
+    pub fn into_inner(self) -> Server {
+        self.server
+    }
+}
+
+impl Stream for GenerateEvents {
+    type Item = Result<GenerateEvent, ServerError>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(generate) = this.changed.take() {
+            return Poll::Ready(Some(Ok(GenerateEvent::GenerateRequested(generate))));
+        }
+
+        let command = match ready!(this.server.poll_next_unpin(cx)) {
+            Some(Ok(command)) => command,
+            Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+            None => return Poll::Ready(None),
+        };
+
+        if let ClientCommand::ChangeSettings(settings) = &command {
+            let generate = settings
+                .monitor_ui_settings
+                .as_ref()
+                .and_then(|ui_settings| ui_settings.generate);
+
+            if let Some(generate) = generate.filter(|generate| *generate != this.generate) {
+                trace!(generate, "Mocked data generation changed");
+                this.generate = generate;
+                this.changed = Some(generate);
+            }
+        }
+
+        Poll::Ready(Some(Ok(GenerateEvent::Command(command))))
+    }
+}
@@ -38,8 +38,81 @@
 // the `docsrs` configuration attribute is defined
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+/// The close code of a websocket Close frame, e.g. of [`Client::close_with`] and [`ServerEvent::Close`].
+pub use tokio_websockets::CloseCode;
+
 #[doc(inline)]
 pub use api::*;
+#[doc(inline)]
+pub use audit::*;
+#[doc(inline)]
+pub use batch::*;
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+#[doc(inline)]
+pub use blocking::*;
+#[doc(inline)]
+pub use broadcast::*;
+#[doc(inline)]
+pub use buffered::*;
+#[doc(inline)]
+pub use debounce::*;
+#[doc(inline)]
+pub use dedup::*;
+#[doc(inline)]
+pub use events::*;
+#[doc(inline)]
+pub use generate::*;
+#[cfg(feature = "test-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+#[doc(inline)]
+pub use mock::*;
+#[doc(inline)]
+pub use multiplexer::*;
+#[doc(inline)]
+pub use plotter::*;
+#[doc(inline)]
+pub use queue::*;
+#[doc(inline)]
+pub use reconnect::*;
+#[doc(inline)]
+pub use serve::*;
+#[doc(inline)]
+pub use session::*;
+#[doc(inline)]
+pub use sink::*;
+#[doc(inline)]
+pub use stats::*;
+#[doc(inline)]
+pub use transport::*;
+#[doc(inline)]
+pub use violation::*;
+#[doc(inline)]
+pub use window::*;
 
 mod api;
+mod audit;
+mod batch;
+#[cfg(feature = "blocking")]
+mod blocking;
+mod broadcast;
+mod buffered;
+mod debounce;
+mod dedup;
+mod events;
+mod generate;
+mod log;
+#[cfg(feature = "test-util")]
+mod mock;
+mod multiplexer;
+mod plotter;
 pub mod protocol;
+mod queue;
+mod reconnect;
+mod serve;
+mod session;
+mod sink;
+mod stats;
+mod transport;
+mod violation;
+mod window;
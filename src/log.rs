@@ -0,0 +1,58 @@
+//! The `tracing` macros used by the crate, no-ops without the `tracing` feature.
+//!
+//! The no-ops only borrow the logged values and format arguments, so the same variables are used
+//! with and without the feature.
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::{debug, error, info, trace};
+
+#[cfg(not(feature = "tracing"))]
+mod noop {
+    /// Borrows the values of the `tracing` fields and format arguments.
+    macro_rules! fields {
+        () => {};
+        ($message:literal $(, $arg:expr)* $(,)?) => {
+            // also uses the variables captured by the message
+            let _ = format_args!($message $(, $arg)*);
+        };
+        ($name:ident = ? $value:expr $(, $($rest:tt)*)?) => {
+            let _ = &$value;
+            $($crate::log::fields!($($rest)*);)?
+        };
+        ($name:ident = % $value:expr $(, $($rest:tt)*)?) => {
+            let _ = &$value;
+            $($crate::log::fields!($($rest)*);)?
+        };
+        ($name:ident = $value:expr $(, $($rest:tt)*)?) => {
+            let _ = &$value;
+            $($crate::log::fields!($($rest)*);)?
+        };
+        (? $value:ident $(, $($rest:tt)*)?) => {
+            let _ = &$value;
+            $($crate::log::fields!($($rest)*);)?
+        };
+        (% $value:ident $(, $($rest:tt)*)?) => {
+            let _ = &$value;
+            $($crate::log::fields!($($rest)*);)?
+        };
+        ($value:ident $(, $($rest:tt)*)?) => {
+            let _ = &$value;
+            $($crate::log::fields!($($rest)*);)?
+        };
+    }
+
+    macro_rules! noop {
+        ($($arg:tt)*) => {{
+            $crate::log::fields!($($arg)*);
+        }};
+    }
+
+    pub(crate) use fields;
+    pub(crate) use noop as debug;
+    pub(crate) use noop as error;
+    pub(crate) use noop as info;
+    pub(crate) use noop as trace;
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) use noop::{debug, error, fields, info, trace};
@@ -0,0 +1,129 @@
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::DuplexStream;
+use tokio_websockets::{ClientBuilder, Message, ServerBuilder, WebSocketStream};
+
+use crate::{
+    api::serialize,
+    protocol::{ClientCommand, Data, MiddlewareCommand, MonitorSettings},
+    Client, Server, ServerError,
+};
+
+/// The size of the in-memory connection buffers of [`test_pair`].
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Connects a [`Client`] and a [`Server`] to a [`MockUi`] over an in-memory connection,
+/// e.g. for testing a connection handler without a listener and the Arduino Serial Plotter UI.
+///
+/// Available with the `test-util` feature.
+///
+/// ```
+/// use arduino_plotter::{
+///     protocol::{ClientCommand, MonitorSettings},
+///     test_pair, Client, MockUiMessage, Server,
+/// };
+/// use futures_util::StreamExt;
+///
+/// // echoes the changed settings back to the UI
+/// async fn handle(client: Client, mut server: Server) {
+///     while let Some(Ok(command)) = server.next().await {
+///         if let ClientCommand::ChangeSettings(settings) = command {
+///             client.send_full_settings(settings).await.unwrap();
+///         }
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (client, server, mut ui) = test_pair();
+///     tokio::spawn(handle(client, server));
+///
+///     let dark_theme = MonitorSettings::builder().dark_theme(true).build();
+///     ui.send(&ClientCommand::ChangeSettings(dark_theme))
+///         .await
+///         .unwrap();
+///
+///     match ui.recv().await.unwrap().unwrap() {
+///         MockUiMessage::Settings(settings) => {
+///             assert_eq!(Some(true), settings.monitor_ui_settings.unwrap().dark_theme)
+///         }
+///         other => panic!("Unexpected message: {other:?}"),
+///     }
+/// }
+/// ```
+pub fn test_pair() -> (Client, Server, MockUi) {
+    let (ui_io, plotter_io) = tokio::io::duplex(BUFFER_SIZE);
+
+    let (client, server) = Client::from_transport(ServerBuilder::new().serve(plotter_io));
+    let ui = MockUi {
+        ws: ClientBuilder::new().take_over(ui_io),
+    };
+
+    (client, server, ui)
+}
+
+/// A message sent to the Arduino Serial Plotter UI, received by the [`MockUi`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockUiMessage {
+    /// An `ON_SETTINGS_DID_CHANGE` command, see [`MiddlewareCommand`].
+    Settings(MonitorSettings),
+    /// A Data lines message.
+    Data(Data<String>),
+}
+
+/// The Arduino Serial Plotter UI side of a [`test_pair`].
+///
+/// Available with the `test-util` feature.
+#[derive(Debug)]
+pub struct MockUi {
+    ws: WebSocketStream<DuplexStream>,
+}
+
+impl MockUi {
+    /// Sends a [`ClientCommand`] as the Arduino Serial Plotter UI does.
+    pub async fn send(&mut self, command: &ClientCommand) -> Result<(), tokio_websockets::Error> {
+        let json = serialize(command);
+
+        self.send_text(json).await
+    }
+
+    /// Sends a text message as is, e.g. an invalid command.
+    pub async fn send_text(&mut self, text: String) -> Result<(), tokio_websockets::Error> {
+        self.ws.send(Message::text(text)).await
+    }
+
+    /// Receives the next settings or Data lines message,
+    /// `None` when the [`Client`] has closed the connection.
+    ///
+    /// A message which is neither is a [`ServerError::Unrecognized`].
+    pub async fn recv(&mut self) -> Option<Result<MockUiMessage, ServerError>> {
+        let message = loop {
+            match self.ws.next().await? {
+                Ok(message) if message.is_ping() || message.is_pong() => continue,
+                Ok(message) if message.is_close() => return None,
+                Ok(message) => break message,
+                Err(err) => return Some(Err(err.into())),
+            }
+        };
+
+        let Some(text) = message.as_text() else {
+            return Some(Err(ServerError::NonTextMessage));
+        };
+
+        let received = if text.trim_start().starts_with('[') {
+            serde_json::from_str(text).map(MockUiMessage::Data)
+        } else {
+            serde_json::from_str(text)
+                .map(|MiddlewareCommand(settings)| MockUiMessage::Settings(settings))
+        };
+
+        Some(received.map_err(|source| ServerError::Unrecognized {
+            raw: text.to_string(),
+            source,
+        }))
+    }
+
+    /// Closes the connection, the [`Server`] stream ends.
+    pub async fn close(mut self) -> Result<(), tokio_websockets::Error> {
+        self.ws.close().await
+    }
+}
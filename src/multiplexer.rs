@@ -0,0 +1,232 @@
+use core::{pin::Pin, time::Duration};
+
+use futures_util::{
+    stream::{self, SelectAll},
+    Stream, StreamExt,
+};
+use tokio::time::MissedTickBehavior;
+
+use crate::log::{debug, trace};
+use crate::{
+    protocol::{is_valid_label, EndOfLine, InvalidLabel},
+    Client, SendError,
+};
+
+type SourceStream = Pin<Box<dyn Stream<Item = (usize, Option<f64>)> + Send>>;
+
+/// What the [`Multiplexer`] should do with a label once its source stream has ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceEnd {
+    /// Remove the label from all subsequent data frames.
+    #[default]
+    Drop,
+    /// Keep sending the last received value of the label.
+    HoldLast,
+}
+
+/// Combines multiple `(label, Stream<Item = f64>)` sources into a single
+/// data line which is sent with [`Client::send`] at a common cadence.
+///
+/// On every tick the latest value of each label is used, labels which haven't
+/// produced a value yet are skipped and no frame is sent if there are no values at all.
+/// The [`Multiplexer`] stops once all of its sources have ended, after sending a final frame
+/// of the held values with [`SourceEnd::HoldLast`].
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use arduino_plotter::{Client, Multiplexer, SourceEnd};
+/// use futures_util::StreamExt;
+/// use tokio_websockets::{ClientBuilder, ServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let mut ui = ClientBuilder::new().take_over(ui_io);
+///     let (ws_sink, _ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+///     let client = Client::new(ws_sink);
+///
+///     let temperature = futures_util::stream::iter([21.5, 21.7, 22.0]);
+///     let humidity = futures_util::stream::iter([40.0, 41.5]);
+///
+///     let mut multiplexer = Multiplexer::new(Duration::from_millis(100))
+///         .on_source_end(SourceEnd::HoldLast);
+///     multiplexer
+///         .register("temperature", temperature)
+///         .unwrap()
+///         .register("humidity", humidity)
+///         .unwrap();
+///     multiplexer.run(&client).await.unwrap();
+///
+///     // the final frame has the last value of every source
+///     loop {
+///         let message = ui.next().await.unwrap().unwrap();
+///         if message.as_text() == Some(r#"["temperature:22,humidity:41.5\n"]"#) {
+///             break;
+///         }
+///     }
+/// }
+/// ```
+pub struct Multiplexer {
+    interval: Duration,
+    on_source_end: SourceEnd,
+    line_ending: EndOfLine,
+    labels: Vec<String>,
+    sources: Vec<SourceStream>,
+}
+
+impl Multiplexer {
+    /// Creates a new [`Multiplexer`] which sends a data frame every `interval`.
+    ///
+    /// By default ended sources are dropped ([`SourceEnd::Drop`]) and lines
+    /// are terminated with [`EndOfLine::NewLine`].
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            on_source_end: SourceEnd::default(),
+            line_ending: EndOfLine::NewLine,
+            labels: Vec::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Sets what happens to a label once its source has ended.
+    pub fn on_source_end(mut self, on_source_end: SourceEnd) -> Self {
+        self.on_source_end = on_source_end;
+        self
+    }
+
+    /// Sets the [`EndOfLine`] appended to each sent data line.
+    pub fn line_ending(mut self, line_ending: EndOfLine) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Registers a new source of values for the given label.
+    ///
+    /// Fails with an [`InvalidLabel`] when the label is empty or contains a label (`:`)
+    /// or values (`,`, ` `, `\t`) separator, same as [`DataBuilder::field`],
+    /// and the source is not registered.
+    ///
+    /// ```
+    /// use core::time::Duration;
+    ///
+    /// use arduino_plotter::{protocol::InvalidLabel, Multiplexer};
+    ///
+    /// let mut multiplexer = Multiplexer::new(Duration::from_millis(100));
+    ///
+    /// assert!(multiplexer.register("temp", futures_util::stream::empty()).is_ok());
+    /// assert_eq!(
+    ///     Some(InvalidLabel("air temp".to_string())),
+    ///     multiplexer
+    ///         .register("air temp", futures_util::stream::empty())
+    ///         .err()
+    /// );
+    /// ```
+    ///
+    /// [`DataBuilder::field`]: crate::protocol::DataBuilder::field
+    pub fn register<S>(
+        &mut self,
+        label: impl Into<String>,
+        source: S,
+    ) -> Result<&mut Self, InvalidLabel>
+    where
+        S: Stream<Item = f64> + Send + 'static,
+    {
+        let label = label.into();
+        if !is_valid_label(&label) {
+            return Err(InvalidLabel(label));
+        }
+
+        let index = self.labels.len();
+        self.labels.push(label);
+
+        // `None` marks the end of the source
+        let source = source
+            .map(move |value| (index, Some(value)))
+            .chain(stream::once(async move { (index, None) }));
+        self.sources.push(Box::pin(source));
+
+        Ok(self)
+    }
+
+    /// Runs the [`Multiplexer`] until all sources have ended or sending a data frame fails.
+    pub async fn run(self, client: &Client) -> Result<(), SendError> {
+        let Self {
+            interval,
+            on_source_end,
+            line_ending,
+            labels,
+            sources,
+        } = self;
+
+        let mut latest: Vec<Option<f64>> = vec![None; labels.len()];
+        let mut sources: SelectAll<SourceStream> = stream::select_all(sources);
+
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                next = sources.next() => match next {
+                    Some((index, Some(value))) => latest[index] = Some(value),
+                    Some((index, None)) => {
+                        debug!(label = labels[index], "Multiplexer source ended");
+
+                        if on_source_end == SourceEnd::Drop {
+                            latest[index] = None;
+                        }
+                    }
+                    None => {
+                        debug!("All multiplexer sources have ended");
+
+                        // the held values might have changed since the last tick
+                        if let Some(line) = multiplexed_line(&labels, &latest, line_ending) {
+                            trace!(line, "Sending the final multiplexed data line");
+                            client.send(&[&line]).await?;
+                        }
+
+                        return Ok(());
+                    }
+                },
+                _ = ticker.tick() => {
+                    if let Some(line) = multiplexed_line(&labels, &latest, line_ending) {
+                        trace!(line, "Sending multiplexed data line");
+                        client.send(&[&line]).await?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The data line of the latest values, `None` when there are no values at all.
+fn multiplexed_line(
+    labels: &[String],
+    latest: &[Option<f64>],
+    line_ending: EndOfLine,
+) -> Option<String> {
+    let line = labels
+        .iter()
+        .zip(latest)
+        .filter_map(|(label, value)| value.map(|value| format!("{label}:{value}")))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    if line.is_empty() {
+        return None;
+    }
+
+    Some(format!("{line}{line_ending}"))
+}
+
+impl core::fmt::Debug for Multiplexer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Multiplexer")
+            .field("interval", &self.interval)
+            .field("on_source_end", &self.on_source_end)
+            .field("line_ending", &self.line_ending)
+            .field("labels", &self.labels)
+            .finish_non_exhaustive()
+    }
+}
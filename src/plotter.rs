@@ -0,0 +1,409 @@
+use core::time::Duration;
+use std::{collections::VecDeque, net::SocketAddr, task::Poll};
+
+use futures_util::{Stream, StreamExt};
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio_websockets::ServerBuilder;
+
+use crate::log::{debug, trace};
+use crate::{
+    protocol::{ClientCommand, MonitorSettings},
+    Client, HandshakeError, SendError, Server, ServerError,
+};
+
+/// Errors when waiting for the Arduino Serial Plotter UI to confirm the sent settings.
+#[derive(Debug, Error)]
+pub enum ConfirmError {
+    /// Sending the settings failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+    /// The UI did not confirm the settings in time.
+    #[error("Settings were not confirmed in time")]
+    Timeout,
+    /// The connection was closed before the settings were confirmed.
+    #[error("Connection closed before the settings were confirmed")]
+    Closed,
+}
+
+/// A single connection to the Arduino Serial Plotter UI owning both the [`Client`]
+/// and the [`Server`].
+///
+/// The [`Plotter`] is itself a [`Stream`] of the received [`ClientCommand`]s.
+///
+/// ```no_run
+/// use core::time::Duration;
+///
+/// use arduino_plotter::{protocol::MonitorSettings, Client, Plotter, Server};
+/// use futures_util::StreamExt;
+///
+/// async fn run(client: Client, server: Server, settings: MonitorSettings) {
+///     let mut plotter = Plotter::new(client, server);
+///
+///     match plotter
+///         .set_settings_confirmed(settings, Duration::from_secs(1))
+///         .await
+///     {
+///         Ok(confirmed) => println!("UI confirmed: {confirmed:?}"),
+///         Err(err) => println!("Settings not confirmed: {err}"),
+///     }
+///
+///     // commands received while waiting for the confirmation are not lost
+///     while let Some(command) = plotter.next().await {
+///         println!("Received: {command:?}");
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Plotter {
+    client: Client,
+    server: Server,
+    /// Received while waiting for a settings confirmation.
+    pending: VecDeque<Result<ClientCommand, ServerError>>,
+}
+
+impl Plotter {
+    pub fn new(client: Client, server: Server) -> Self {
+        Self {
+            client,
+            server,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// The [`Client`] of the connection.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Splits the [`Plotter`] into its [`Client`] and [`Server`].
+    ///
+    /// Any commands received while waiting for a settings confirmation
+    /// which haven't been consumed from the [`Plotter`] stream are dropped.
+    pub fn into_parts(self) -> (Client, Server) {
+        (self.client, self.server)
+    }
+
+    /// Sends the [`MonitorSettings`] and waits for the Arduino Serial Plotter UI
+    /// to confirm them with a [`ClientCommand::ChangeSettings`] echo.
+    ///
+    /// A `ChangeSettings` confirms the settings when it contains all the sent values
+    /// and it is returned.
+    /// All the other received commands are kept and yielded by the [`Plotter`] stream.
+    ///
+    /// A port change is refused with [`SendError::PortChangeWouldDisconnect`] like
+    /// [`Client::set_monitor_settings`] does, as the UI can't confirm settings over
+    /// the connection they close.
+    pub async fn set_settings_confirmed(
+        &mut self,
+        monitor_settings: MonitorSettings,
+        timeout: Duration,
+    ) -> Result<MonitorSettings, ConfirmError> {
+        self.client
+            .set_monitor_settings(monitor_settings.clone())
+            .await?;
+
+        let (server, pending) = (&mut self.server, &mut self.pending);
+        let confirmation = async {
+            while let Some(result) = server.next().await {
+                match result {
+                    Ok(ClientCommand::ChangeSettings(received))
+                        if confirms(&received, &monitor_settings) =>
+                    {
+                        trace!("Settings confirmed by the UI");
+
+                        return Ok(received);
+                    }
+                    other => pending.push_back(other),
+                }
+            }
+
+            Err(ConfirmError::Closed)
+        };
+
+        tokio::time::timeout(timeout, confirmation)
+            .await
+            .unwrap_or(Err(ConfirmError::Timeout))
+    }
+}
+
+impl Stream for Plotter {
+    type Item = Result<ClientCommand, ServerError>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match this.pending.pop_front() {
+            Some(pending) => Poll::Ready(Some(pending)),
+            None => this.server.poll_next_unpin(cx),
+        }
+    }
+}
+
+/// Errors when setting up a connection with [`PlotterBuilder::build`].
+#[derive(Debug, Error)]
+pub enum BuildError {
+    /// Binding the address or accepting a connection failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Sending the initial settings failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+    /// The settings handshake of the UI failed.
+    #[error(transparent)]
+    Handshake(#[from] HandshakeError),
+}
+
+/// Sets up a single connection to the Arduino Serial Plotter UI, i.e.:
+/// 1. binds the address (`127.0.0.1:3030` by default)
+/// 2. accepts the first connection with a successful websocket handshake,
+///    the failed ones are logged and skipped
+/// 3. sends the full initial settings with the bound port
+/// 4. waits for the settings handshake of the UI, see [`Server::await_handshake`]
+///
+/// and returns the [`Plotter`] of the connection with the settings of the UI.
+///
+/// ```no_run
+/// use core::time::Duration;
+///
+/// use arduino_plotter::{
+///     protocol::{EndOfLine, MonitorModelState, MonitorSettings},
+///     BuildError, PlotterBuilder,
+/// };
+///
+/// async fn run() -> Result<(), BuildError> {
+///     let initial_settings = MonitorSettings {
+///         monitor_ui_settings: Some(MonitorModelState {
+///             connected: Some(true),
+///             line_ending: Some(EndOfLine::NewLine),
+///             ..Default::default()
+///         }),
+///         ..Default::default()
+///     };
+///
+///     let (plotter, ui_settings) = PlotterBuilder::new()
+///         .bind(([127, 0, 0, 1], 3030).into())
+///         .initial_settings(initial_settings)
+///         .handshake_timeout(Duration::from_secs(10))
+///         .build()
+///         .await?;
+///     println!("UI settings: {ui_settings:?}");
+///
+///     plotter.client().send(&["L1:1,L2:2\n"]).await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PlotterBuilder {
+    addr: SocketAddr,
+    initial_settings: Option<MonitorSettings>,
+    handshake_timeout: Duration,
+    frame_validation: bool,
+}
+
+impl Default for PlotterBuilder {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], 3030)),
+            initial_settings: None,
+            handshake_timeout: Duration::from_secs(5),
+            frame_validation: false,
+        }
+    }
+}
+
+impl PlotterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The address to listen at for the connection of the UI.
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    /// The settings sent to the UI once it's connected.
+    pub fn initial_settings(mut self, initial_settings: MonitorSettings) -> Self {
+        self.initial_settings = Some(initial_settings);
+        self
+    }
+
+    /// How long to wait for the settings handshake of the UI (5 seconds by default).
+    pub fn handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Validates the sent Data frames, see [`Client::set_frame_validation`].
+    pub fn frame_validation(mut self, frame_validation: bool) -> Self {
+        self.frame_validation = frame_validation;
+        self
+    }
+
+    /// Binds the address and waits for the UI to connect,
+    /// see [`PlotterBuilder::listen`] and [`PlotterListener::accept`].
+    pub async fn build(self) -> Result<(Plotter, MonitorSettings), BuildError> {
+        self.listen().await?.accept().await
+    }
+
+    /// Binds the address without waiting for the UI yet, e.g. to learn the port
+    /// with [`PlotterListener::local_addr`] when binding to port `0` (an ephemeral port).
+    ///
+    /// ```
+    /// use arduino_plotter::{
+    ///     protocol::{MiddlewareCommand, MonitorSettings},
+    ///     PlotterBuilder,
+    /// };
+    /// use futures_util::{SinkExt, StreamExt};
+    /// use tokio_websockets::{ClientBuilder, Message};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let listener = PlotterBuilder::new()
+    ///         .bind(([127, 0, 0, 1], 0).into())
+    ///         .initial_settings(MonitorSettings::builder().dark_theme(true).build())
+    ///         .listen()
+    ///         .await
+    ///         .unwrap();
+    ///     let port = listener.local_addr().port();
+    ///     assert_ne!(0, port);
+    ///
+    ///     let ui = tokio::spawn(async move {
+    ///         let uri = format!("ws://127.0.0.1:{port}");
+    ///         let (mut ui, _response) = ClientBuilder::new()
+    ///             .uri(&uri)
+    ///             .unwrap()
+    ///             .connect()
+    ///             .await
+    ///             .unwrap();
+    ///
+    ///         // the full initial settings frame reports the port
+    ///         let initial = ui.next().await.unwrap().unwrap();
+    ///         let MiddlewareCommand(initial) =
+    ///             serde_json::from_str(initial.as_text().unwrap()).unwrap();
+    ///         let ui_settings = initial.monitor_ui_settings.unwrap();
+    ///         assert_eq!(Some(port), ui_settings.ws_port);
+    ///         assert_eq!(Some(true), ui_settings.dark_theme);
+    ///         assert_eq!(Some(true), ui_settings.autoscroll);
+    ///
+    ///         let handshake = r#"{"command": "CHANGE_SETTINGS", "data": {}}"#;
+    ///         ui.send(Message::text(handshake.to_string())).await.unwrap();
+    ///         ui
+    ///     });
+    ///
+    ///     let (_plotter, _ui_settings) = listener.accept().await.unwrap();
+    ///     ui.await.unwrap();
+    /// }
+    /// ```
+    pub async fn listen(self) -> Result<PlotterListener, BuildError> {
+        let listener = TcpListener::bind(self.addr).await?;
+        let local_addr = listener.local_addr()?;
+        debug!(%local_addr, "Waiting for the UI to connect");
+
+        Ok(PlotterListener {
+            listener,
+            local_addr,
+            builder: self,
+        })
+    }
+}
+
+/// A bound [`PlotterBuilder`] waiting for the UI to connect, see [`PlotterBuilder::listen`].
+#[derive(Debug)]
+pub struct PlotterListener {
+    listener: TcpListener,
+    local_addr: SocketAddr,
+    builder: PlotterBuilder,
+}
+
+impl PlotterListener {
+    /// The bound address, with the actual port when binding to port `0`.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Accepts the first connection with a successful websocket handshake and
+    /// returns its [`Plotter`] with the settings of the UI.
+    ///
+    /// The initial settings are completed with the UI defaults, see [`MonitorSettings::into_full`],
+    /// as the UI expects full settings once connected, and sent with the bound port as
+    /// [`MonitorModelState::ws_port`], unless it's already set.
+    ///
+    /// [`MonitorModelState::ws_port`]: crate::protocol::MonitorModelState::ws_port
+    pub async fn accept(self) -> Result<(Plotter, MonitorSettings), BuildError> {
+        let ws_stream = loop {
+            let (stream, ui_addr) = self.listener.accept().await?;
+
+            match ServerBuilder::new().accept(stream).await {
+                Ok(ws_stream) => break ws_stream,
+                Err(err) => debug!(?err, %ui_addr, "Websocket handshake failed"),
+            }
+        };
+
+        let (client, mut server) = Client::from_transport(ws_stream);
+        client.set_frame_validation(self.builder.frame_validation);
+
+        let mut initial_settings = self
+            .builder
+            .initial_settings
+            .unwrap_or_default()
+            .into_full();
+        initial_settings
+            .monitor_ui_settings
+            .get_or_insert_with(Default::default)
+            .ws_port
+            .get_or_insert(self.local_addr.port());
+        // the UI is already connected to the bound port
+        client
+            .set_monitor_settings_allow_port_change(initial_settings)
+            .await?;
+
+        let ui_settings = server
+            .await_handshake(self.builder.handshake_timeout)
+            .await?;
+
+        Ok((Plotter::new(client, server), ui_settings))
+    }
+}
+
+/// Whether the `received` settings contain all the values of the `sent` ones.
+fn confirms(received: &MonitorSettings, sent: &MonitorSettings) -> bool {
+    let ui_confirmed = match (&sent.monitor_ui_settings, &received.monitor_ui_settings) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(sent), Some(received)) => {
+            fn matches<T: PartialEq>(sent: &Option<T>, received: &Option<T>) -> bool {
+                sent.is_none() || sent == received
+            }
+
+            matches(&sent.autoscroll, &received.autoscroll)
+                && matches(&sent.timestamp, &received.timestamp)
+                && matches(&sent.line_ending, &received.line_ending)
+                && matches(&sent.interpolate, &received.interpolate)
+                && matches(&sent.dark_theme, &received.dark_theme)
+                && matches(&sent.ws_port, &received.ws_port)
+                && matches(&sent.serial_port, &received.serial_port)
+                && matches(&sent.connected, &received.connected)
+        }
+    };
+
+    let pluggable_confirmed = match (
+        &sent.pluggable_monitor_settings,
+        &received.pluggable_monitor_settings,
+    ) {
+        (None, _) => true,
+        (Some(sent), received) => sent.iter().all(|(id, sent_setting)| {
+            received
+                .as_ref()
+                .and_then(|received| received.get(id))
+                .is_some_and(|received| received.selected_value == sent_setting.selected_value)
+        }),
+    };
+
+    ui_confirmed && pluggable_confirmed
+}
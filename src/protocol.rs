@@ -4,44 +4,1162 @@ use std::{
 };
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use parse_display::{Display, FromStr};
 
+pub mod snake_case;
+
 /// The generic Command structure defined by the Arduino serial plotter README.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Command<T> {
     pub command: CommandName,
     pub data: T,
 }
 
-/// Data lines message that can be send to the Arduino serial plotter
+/// Data lines message that can be send to the Arduino serial plotter
+///
+/// <https://docs.arduino.cc/software/ide-v2/tutorials/ide-v2-serial-plotter>
+///
+/// ```
+/// use arduino_plotter::protocol::Data;
+///
+/// // data with no line ending
+/// let data = Data(vec![
+///     "L1:1,L2:2,L3:3,L4:4".to_string(),
+///     "Label_1:99,Label_2:98,Label_3:97,Label_4:96".to_string(),
+/// ]);
+/// let data_json = serde_json::json!([
+///     "L1:1,L2:2,L3:3,L4:4",
+///     "Label_1:99,Label_2:98,Label_3:97,Label_4:96"
+/// ]);
+/// let from_json = serde_json::from_value::<Data<String>>(data_json).expect("should be valid");
+///
+/// assert_eq!(data, from_json);
+/// ```
+///
+/// The lines only need to be serializable, e.g. as strings the UI can plot:
+///
+/// ```
+/// use arduino_plotter::protocol::Data;
+/// use serde::Serialize;
+///
+/// #[derive(Clone, Serialize)]
+/// #[serde(into = "String")]
+/// struct Reading(f64);
+///
+/// impl From<Reading> for String {
+///     fn from(reading: Reading) -> Self {
+///         format!("temp:{}\n", reading.0)
+///     }
+/// }
+///
+/// let data = Data(vec![Reading(21.5), Reading(22.0)]);
+/// assert_eq!(
+///     r#"["temp:21.5\n","temp:22\n"]"#,
+///     serde_json::to_string(&data).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Data<T>(pub Vec<T>);
+
+impl<T> IntoIterator for Data<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Data<T> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// A single line frame of positional (unlabeled) values, e.g. `1.5,-2,3\n`.
+///
+/// The values are formatted with [`Display`](core::fmt::Display) which is locale-independent,
+/// i.e. always with `.` decimals, and never uses a scientific notation.
+/// The line is terminated with [`EndOfLine::NewLine`].
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::Data;
+///
+/// let data = Data::from(&[1.5, -2.0, 0.000_001, 1e21][..]);
+/// assert_eq!(
+///     vec!["1.5,-2,0.000001,1000000000000000000000\n".to_string()],
+///     data.0
+/// );
+///
+/// assert_eq!(
+///     vec!["3.14159,-0,1000000\n".to_string()],
+///     Data::from(&[3.14159, -0.0, 1000000.0][..]).0
+/// );
+///
+/// assert_eq!(vec!["\n".to_string()], Data::from(&[][..]).0);
+/// ```
+impl From<&[f64]> for Data<String> {
+    fn from(values: &[f64]) -> Self {
+        let line = values
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Self(vec![line + &EndOfLine::NewLine.to_string()])
+    }
+}
+
+impl Data<String> {
+    /// Splits a raw multiline string, e.g. a serial dump, on the `eol` into the lines
+    /// of a [`Data`] frame.
+    ///
+    /// The lines are split with [`EndOfLine::split`] and with `keep_eol` they keep
+    /// their line ending, otherwise it's stripped.
+    /// An empty line after the final line ending is dropped and with
+    /// [`EndOfLine::NoLineEnding`] the whole string is a single line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{Data, EndOfLine};
+    ///
+    /// let data = |lines: &[&str]| Data(lines.iter().map(ToString::to_string).collect());
+    ///
+    /// assert_eq!(
+    ///     data(&["L1:1", "L1:2"]),
+    ///     Data::from_multiline("L1:1\nL1:2\n", EndOfLine::NewLine, false)
+    /// );
+    /// assert_eq!(
+    ///     data(&["L1:1\n", "L1:2\n"]),
+    ///     Data::from_multiline("L1:1\nL1:2\n", EndOfLine::NewLine, true)
+    /// );
+    /// assert_eq!(
+    ///     data(&["L1:1", "L1:2"]),
+    ///     Data::from_multiline("L1:1\rL1:2", EndOfLine::CarriageReturn, false)
+    /// );
+    /// assert_eq!(
+    ///     data(&["L1:1\r", "L1:2"]),
+    ///     Data::from_multiline("L1:1\rL1:2", EndOfLine::CarriageReturn, true)
+    /// );
+    /// assert_eq!(
+    ///     data(&["L1:1", "", "L1:2"]),
+    ///     Data::from_multiline("L1:1\r\n\r\nL1:2\r\n", EndOfLine::CarriageReturnNewLine, false)
+    /// );
+    /// assert_eq!(
+    ///     data(&["L1:1\r\n", "\r\n", "L1:2\r\n"]),
+    ///     Data::from_multiline("L1:1\r\n\r\nL1:2\r\n", EndOfLine::CarriageReturnNewLine, true)
+    /// );
+    /// assert_eq!(
+    ///     data(&["L1:1\nL1:2\n"]),
+    ///     Data::from_multiline("L1:1\nL1:2\n", EndOfLine::NoLineEnding, false)
+    /// );
+    /// assert_eq!(
+    ///     data(&[]),
+    ///     Data::from_multiline("", EndOfLine::NewLine, true)
+    /// );
+    /// ```
+    pub fn from_multiline(string: &str, eol: EndOfLine, keep_eol: bool) -> Self {
+        let mut lines: Vec<String> = eol.split(string).map(ToString::to_string).collect();
+
+        if keep_eol {
+            let eol = eol.to_string();
+            let count = lines.len();
+            // the last line is terminated only when the whole string is
+            let last_terminated = string.ends_with(eol.as_str());
+
+            for (index, line) in lines.iter_mut().enumerate() {
+                if index + 1 < count || last_terminated {
+                    line.push_str(&eol);
+                }
+            }
+        }
+
+        Self(lines)
+    }
+
+    /// Streams each line of a JSON Data lines message from the `reader` to the
+    /// `visitor` without collecting the whole message into a [`Data`].
+    ///
+    /// Useful for processing huge captured frames with a bounded memory usage,
+    /// as only a single line is kept in memory at a time.
+    /// Returns the number of visited lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::Data;
+    ///
+    /// let json = r#"["L1:1,L2:2", "L1:3,L2:4", "L1:5,L2:6"]"#;
+    ///
+    /// let mut lines = vec![];
+    /// let count = Data::visit_lines(json.as_bytes(), |line| lines.push(line.to_string()))
+    ///     .expect("Valid data lines");
+    ///
+    /// assert_eq!(3, count);
+    /// assert_eq!(vec!["L1:1,L2:2", "L1:3,L2:4", "L1:5,L2:6"], lines);
+    ///
+    /// // not a data lines message
+    /// assert!(Data::visit_lines(r#"{"command": "SEND_MESSAGE"}"#.as_bytes(), |_| {}).is_err());
+    /// ```
+    pub fn visit_lines<R, F>(reader: R, visitor: F) -> Result<usize, serde_json::Error>
+    where
+        R: std::io::Read,
+        F: FnMut(&str),
+    {
+        use serde::de::{DeserializeSeed, SeqAccess, Visitor};
+
+        struct Lines<F>(F);
+
+        impl<'de, F: FnMut(&str)> Visitor<'de> for Lines<F> {
+            type Value = usize;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("an array of data lines")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<usize, A::Error> {
+                let mut count = 0;
+                while seq.next_element_seed(Line(&mut self.0))?.is_some() {
+                    count += 1;
+                }
+
+                Ok(count)
+            }
+        }
+
+        struct Line<'a, F>(&'a mut F);
+
+        impl<'de, F: FnMut(&str)> DeserializeSeed<'de> for Line<'_, F> {
+            type Value = ();
+
+            fn deserialize<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<(), D::Error> {
+                deserializer.deserialize_str(self)
+            }
+        }
+
+        impl<'de, F: FnMut(&str)> Visitor<'de> for Line<'_, F> {
+            type Value = ();
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a data line string")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, line: &str) -> Result<(), E> {
+                (self.0)(line);
+
+                Ok(())
+            }
+        }
+
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let count = serde::Deserializer::deserialize_seq(&mut deserializer, Lines(visitor))?;
+        deserializer.end()?;
+
+        Ok(count)
+    }
+}
+
+impl<T: AsRef<str>> Data<T> {
+    /// Verifies that, interpreted as a matrix, all the lines have the same number
+    /// of fields as the first one, e.g. when a sensor dropped out and shortened a line.
+    ///
+    /// The fields are separated with `,`, ` ` or `\t`, same as in [`parse_variables`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{Data, InconsistentColumns};
+    ///
+    /// let consistent = Data(vec!["L1:1,L2:2\n", "L1:3 L2:4\n"]);
+    /// assert_eq!(Ok(()), consistent.validate_consistent());
+    ///
+    /// let inconsistent = Data(vec!["L1:1,L2:2\n", "L1:3\n", "L1:5,L2:6\n", "\n"]);
+    /// assert_eq!(
+    ///     Err(InconsistentColumns {
+    ///         expected: 2,
+    ///         mismatched: vec![(1, 1), (3, 0)],
+    ///     }),
+    ///     inconsistent.validate_consistent()
+    /// );
+    /// ```
+    pub fn validate_consistent(&self) -> Result<(), InconsistentColumns> {
+        validate_consistent(&self.0)
+    }
+
+    /// Parses every line into its optionally labeled values, e.g. `L1:1,2` into
+    /// `[(Some("L1"), 1.0), (None, 2.0)]`.
+    ///
+    /// The fields are split same as in the Arduino Serial Plotter UI and [`parse_variables`],
+    /// i.e. on `,`, ` ` or `\t`, skipping the empty fields (e.g. of a trailing `,`),
+    /// and a field is labeled when it has a `:`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{Data, ParseError};
+    ///
+    /// let data = Data(vec!["L1:1,L2:-2.5,\n", "1.5e3,,L2:4\n"]);
+    /// assert_eq!(
+    ///     Ok(vec![
+    ///         vec![(Some("L1".to_string()), 1.0), (Some("L2".to_string()), -2.5)],
+    ///         vec![(None, 1500.0), (Some("L2".to_string()), 4.0)],
+    ///     ]),
+    ///     data.parse()
+    /// );
+    ///
+    /// let bad = Data(vec!["L1:1\n", "L1:1,L2:oops\n"]);
+    /// assert_eq!(
+    ///     Err(ParseError::InvalidValue {
+    ///         line: 1,
+    ///         token: "oops".to_string()
+    ///     }),
+    ///     bad.parse()
+    /// );
+    /// ```
+    pub fn parse(&self) -> Result<Vec<ParsedLine>, ParseError> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(line, data_line)| {
+                let variables = parse_variables(data_line.as_ref(), ParseOptions::default())
+                    .map_err(|err| match err {
+                        ParseVariablesError::InvalidValue(token) => {
+                            ParseError::InvalidValue { line, token }
+                        }
+                    })?;
+
+                Ok(variables
+                    .into_iter()
+                    .map(|variable| (variable.label, variable.value))
+                    .collect())
+            })
+            .collect()
+    }
+}
+
+/// The optionally labeled values of a line parsed with [`Data::parse`].
+pub type ParsedLine = Vec<(Option<String>, f64)>;
+
+/// Errors when parsing the lines of a [`Data`] frame with [`Data::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseError {
+    /// The value of a field is not a number.
+    #[error("Invalid value {token:?} on line index {line}")]
+    InvalidValue {
+        /// The index of the line.
+        line: usize,
+        /// The value which is not a number.
+        token: String,
+    },
+}
+
+impl<T: Ord> Data<T> {
+    /// Compares the lines regardless of their order, e.g. when the lines are
+    /// assembled from a `HashMap`, while [`PartialEq`] compares them in order.
+    ///
+    /// The lines are compared as multisets, i.e. the same line has to occur
+    /// the same number of times in both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::Data;
+    ///
+    /// let expected = Data(vec!["L1:1\n", "L2:2\n", "L2:2\n"]);
+    /// let received = Data(vec!["L2:2\n", "L1:1\n", "L2:2\n"]);
+    ///
+    /// assert_ne!(expected, received);
+    /// assert!(expected.eq_unordered(&received));
+    ///
+    /// let duplicated = Data(vec!["L2:2\n", "L1:1\n", "L1:1\n"]);
+    /// assert!(!expected.eq_unordered(&duplicated));
+    /// assert!(!expected.eq_unordered(&Data(vec!["L1:1\n", "L2:2\n"])));
+    /// ```
+    pub fn eq_unordered(&self, other: &Data<T>) -> bool {
+        if self.0.len() != other.0.len() {
+            return false;
+        }
+
+        let mut lines: Vec<&T> = self.0.iter().collect();
+        let mut other_lines: Vec<&T> = other.0.iter().collect();
+        lines.sort_unstable();
+        other_lines.sort_unstable();
+
+        lines == other_lines
+    }
+}
+
+/// Splits a frame without its line ending into its fields (variables), separated with
+/// `,`, ` ` or `\t` same as in the Arduino Serial Plotter UI.
+fn split_fields(frame: &str) -> impl Iterator<Item = &str> {
+    frame
+        .split([',', ' ', '\t'])
+        .filter(|field| !field.is_empty())
+}
+
+/// Whether the `label` is non-empty and without a label (`:`) or fields separator.
+pub(crate) fn is_valid_label(label: &str) -> bool {
+    !label.is_empty() && !label.contains([':', ',', ' ', '\t'])
+}
+
+pub(crate) fn validate_consistent<S: AsRef<str>>(lines: &[S]) -> Result<(), InconsistentColumns> {
+    validate_field_counts(lines.iter().map(|line| field_count(line.as_ref())))
+}
+
+/// The number of fields of a data line, ignoring its line ending.
+pub(crate) fn field_count(line: &str) -> usize {
+    split_fields(EndOfLine::strip(line)).count()
+}
+
+/// Same as [`validate_consistent`] for the already counted fields of every line.
+pub(crate) fn validate_field_counts(
+    counts: impl IntoIterator<Item = usize>,
+) -> Result<(), InconsistentColumns> {
+    let mut counts = counts.into_iter();
+    let Some(expected) = counts.next() else {
+        return Ok(());
+    };
+
+    let mismatched: Vec<(usize, usize)> = counts
+        .enumerate()
+        .map(|(index, count)| (index + 1, count))
+        .filter(|(_, count)| *count != expected)
+        .collect();
+
+    if mismatched.is_empty() {
+        Ok(())
+    } else {
+        Err(InconsistentColumns {
+            expected,
+            mismatched,
+        })
+    }
+}
+
+/// The lines of a [`Data`] frame have different numbers of fields,
+/// see [`Data::validate_consistent`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Expected {expected} fields on every line, found (line index, fields) {mismatched:?}")]
+pub struct InconsistentColumns {
+    /// The number of fields of the first line.
+    pub expected: usize,
+    /// The index and the number of fields of every line which doesn't have the `expected` fields.
+    pub mismatched: Vec<(usize, usize)>,
+}
+
+/// Builds a [`Data`] frame from labeled and unlabeled fields, instead of formatting
+/// the `L1:1,L2:2\n` lines by hand.
+///
+/// The fields are accumulated into the current line until [`DataBuilder::new_line`]
+/// and every line is terminated with the line ending ([`EndOfLine::NewLine`] by default).
+/// The values are formatted with [`Display`](core::fmt::Display).
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::{Data, DataBuilder, EndOfLine, InvalidLabel};
+///
+/// let data = DataBuilder::new()
+///     .field("temp", 21.5)
+///     .unlabeled(40)
+///     .new_line()
+///     .field("temp", 22)
+///     .unlabeled(41)
+///     .line_ending(EndOfLine::CarriageReturnNewLine)
+///     .finish();
+/// assert_eq!(
+///     Ok(Data(vec![
+///         "temp:21.5,40\r\n".to_string(),
+///         "temp:22,41\r\n".to_string()
+///     ])),
+///     data
+/// );
+///
+/// assert_eq!(
+///     Err(InvalidLabel("L1,L2".to_string())),
+///     DataBuilder::new().field("L1,L2", 1).field("L3", 3).finish()
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DataBuilder {
+    lines: Vec<Vec<String>>,
+    line_ending: Option<EndOfLine>,
+    /// The first invalid label, reported by [`DataBuilder::finish`].
+    invalid_label: Option<String>,
+}
+
+impl DataBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a `label:value` field to the current line.
+    ///
+    /// The label should be non-empty and without `:`, `,` or whitespaces,
+    /// otherwise [`DataBuilder::finish`] fails.
+    pub fn field(mut self, label: &str, value: impl core::fmt::Display) -> Self {
+        if self.invalid_label.is_none() && !is_valid_label(label) {
+            self.invalid_label = Some(label.to_string());
+        }
+
+        self.push(format!("{label}:{value}"))
+    }
+
+    /// Adds an unlabeled (positional) value to the current line.
+    pub fn unlabeled(self, value: impl core::fmt::Display) -> Self {
+        self.push(value.to_string())
+    }
+
+    /// Starts a new line, the following fields are added to it.
+    pub fn new_line(mut self) -> Self {
+        self.lines.push(Vec::new());
+        self
+    }
+
+    /// The line ending terminating every line.
+    pub fn line_ending(mut self, line_ending: EndOfLine) -> Self {
+        self.line_ending = Some(line_ending);
+        self
+    }
+
+    /// Returns the [`Data`] frame with the lines which have fields.
+    pub fn finish(self) -> Result<Data<String>, InvalidLabel> {
+        if let Some(label) = self.invalid_label {
+            return Err(InvalidLabel(label));
+        }
+
+        let line_ending = self.line_ending.unwrap_or(EndOfLine::NewLine);
+        let lines = self
+            .lines
+            .into_iter()
+            .filter(|fields| !fields.is_empty())
+            .map(|fields| fields.join(",") + &line_ending.to_string())
+            .collect();
+
+        Ok(Data(lines))
+    }
+
+    fn push(mut self, field: String) -> Self {
+        match self.lines.last_mut() {
+            Some(fields) => fields.push(field),
+            None => self.lines.push(vec![field]),
+        }
+
+        self
+    }
+}
+
+/// The label of a [`DataBuilder`] field, a [`DataSchema`], a [`DataLine`] or a [`LabeledValue`]
+/// is empty or contains a label (`:`) or values (`,`, ` `, `\t`) separator.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Label {0:?} should be non-empty and without `:`, `,` or whitespaces")]
+pub struct InvalidLabel(pub String);
+
+/// The fixed labels every plotted frame (a single Data line) is expected to have.
+///
+/// A frame matches the schema when it has exactly the same labels, in any order,
+/// e.g. `temp:21.5,humidity:40,pressure:1013`.
+/// Matching frames are reordered to the schema's labels order so the columns
+/// of the plot stay consistent.
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::{DataSchema, InvalidLabel, InvalidSchema, SchemaMismatch};
+///
+/// let schema = DataSchema::new(["temp", "humidity", "pressure"]).unwrap();
+///
+/// assert_eq!(
+///     Ok("temp:21.5,humidity:40,pressure:1013".to_string()),
+///     schema.line(&[21.5, 40.0, 1013.0])
+/// );
+/// // the frame is reordered, the line ending is kept
+/// assert_eq!(
+///     Ok("temp:21.5,humidity:40,pressure:1013\n".to_string()),
+///     schema.conform("pressure:1013,temp:21.5,humidity:40\n")
+/// );
+///
+/// assert_eq!(
+///     Err(SchemaMismatch::Count {
+///         expected: 3,
+///         found: 2
+///     }),
+///     schema.conform("temp:21.5,humidity:40")
+/// );
+/// assert_eq!(
+///     Err(SchemaMismatch::UnknownLabel("pressure_hpa".to_string())),
+///     schema.conform("temp:21.5,humidity:40,pressure_hpa:1013")
+/// );
+/// // the values can also be separated with spaces or tabs
+/// assert_eq!(
+///     Ok("temp:21.5,humidity:40,pressure:1013".to_string()),
+///     schema.conform("humidity:40 pressure:1013\ttemp:21.5")
+/// );
+///
+/// assert_eq!(
+///     Err(InvalidSchema::InvalidLabel(InvalidLabel("air temp".to_string()))),
+///     DataSchema::new(["air temp", "humidity"])
+/// );
+/// assert_eq!(
+///     Err(InvalidSchema::DuplicateLabel("temp".to_string())),
+///     DataSchema::new(["temp", "temp"])
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSchema {
+    labels: Vec<String>,
+}
+
+impl DataSchema {
+    /// Creates a schema from the labels of the plotted series in the order of the columns.
+    ///
+    /// Fails when a label is duplicated or it's an [`InvalidLabel`], i.e. it's empty or contains
+    /// a label (`:`) or values (`,`, ` `, `\t`) separator.
+    pub fn new<I>(labels: I) -> Result<Self, InvalidSchema>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let labels: Vec<String> = labels.into_iter().map(Into::into).collect();
+
+        for (index, label) in labels.iter().enumerate() {
+            if !is_valid_label(label) {
+                return Err(InvalidLabel(label.clone()).into());
+            }
+            if labels[..index].contains(label) {
+                return Err(InvalidSchema::DuplicateLabel(label.clone()));
+            }
+        }
+
+        Ok(Self { labels })
+    }
+
+    /// The labels of the schema in the order of the columns.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Formats a frame without a line ending from the `values` given in the order of the labels.
+    pub fn line<V: core::fmt::Display>(&self, values: &[V]) -> Result<String, SchemaMismatch> {
+        if values.len() != self.labels.len() {
+            return Err(SchemaMismatch::Count {
+                expected: self.labels.len(),
+                found: values.len(),
+            });
+        }
+
+        let line = self
+            .labels
+            .iter()
+            .zip(values)
+            .map(|(label, value)| format!("{label}:{value}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Ok(line)
+    }
+
+    /// Verifies that the frame `line` has exactly the labels of the schema
+    /// and returns it with the values reordered to the schema's labels order.
+    ///
+    /// Any trailing line ending of the frame is kept.
+    pub fn conform(&self, line: &str) -> Result<String, SchemaMismatch> {
+        let frame = EndOfLine::strip(line);
+        let line_ending = &line[frame.len()..];
+
+        let pairs = split_fields(frame)
+            .map(|pair| {
+                pair.split_once(':')
+                    .ok_or_else(|| SchemaMismatch::Unlabeled(pair.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if pairs.len() != self.labels.len() {
+            return Err(SchemaMismatch::Count {
+                expected: self.labels.len(),
+                found: pairs.len(),
+            });
+        }
+
+        let mut values = vec![None; self.labels.len()];
+        for (label, value) in pairs {
+            let index = self
+                .labels
+                .iter()
+                .position(|schema_label| schema_label == label)
+                .ok_or_else(|| SchemaMismatch::UnknownLabel(label.to_string()))?;
+
+            if values[index].replace(value).is_some() {
+                return Err(SchemaMismatch::DuplicateLabel(label.to_string()));
+            }
+        }
+
+        // same count, without unknown or duplicate labels, i.e. all values are present
+        let values = values.into_iter().flatten().collect::<Vec<_>>();
+
+        Ok(self.line(&values)? + line_ending)
+    }
+}
+
+/// The labels of a [`DataSchema`] are invalid.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvalidSchema {
+    #[error(transparent)]
+    InvalidLabel(#[from] InvalidLabel),
+    /// The schema has the same label more than once.
+    #[error("Duplicate label {0:?}")]
+    DuplicateLabel(String),
+}
+
+/// The frame does not match the [`DataSchema`] or the [`DataLine`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SchemaMismatch {
+    /// The frame has a different number of values than the schema labels.
+    #[error("Expected {expected} values, found {found}")]
+    Count { expected: usize, found: usize },
+    /// The frame has a label which is not part of the schema.
+    #[error("Unknown label {0:?}")]
+    UnknownLabel(String),
+    /// The frame has the same label more than once.
+    #[error("Duplicate label {0:?}")]
+    DuplicateLabel(String),
+    /// The frame has a value without a label.
+    #[error("A value without a label {0:?}")]
+    Unlabeled(String),
+}
+
+/// A series of a [`DataLine`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Series {
+    /// A value sent with its label, i.e. `label:value`.
+    Labeled(String),
+    /// A value sent without a label, named by the Arduino Serial Plotter UI based on its position.
+    Positional,
+}
+
+impl Series {
+    pub fn labeled(label: impl Into<String>) -> Self {
+        Self::Labeled(label.into())
+    }
+}
+
+/// Formats frames (a single Data line) with mixed labeled and positional series
+/// in a fixed order.
+///
+/// The Arduino Serial Plotter UI splits a line into values and names every
+/// unlabeled value `value N`, where `N` is its 1-based position among **all** the
+/// values of the line, labeled ones included.
+/// E.g. in `temp:21.5,40,pressure:1013,7` the positional values are named
+/// `value 2` and `value 4`, see [`DataLine::names`].
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::{DataLine, SchemaMismatch, Series};
+///
+/// let line = DataLine::new([
+///     Series::labeled("temp"),
+///     Series::Positional,
+///     Series::labeled("pressure"),
+///     Series::Positional,
+/// ])
+/// .unwrap();
+///
+/// assert_eq!(
+///     Ok("temp:21.5,40,pressure:1013,7".to_string()),
+///     line.line(&[21.5, 40.0, 1013.0, 7.0])
+/// );
+/// assert_eq!(vec!["temp", "value 2", "pressure", "value 4"], line.names());
+///
+/// assert_eq!(
+///     Err(SchemaMismatch::Count {
+///         expected: 4,
+///         found: 2
+///     }),
+///     line.line(&[21.5, 40.0])
+/// );
+///
+/// // only positional series
+/// let positional = DataLine::new([Series::Positional, Series::Positional]).unwrap();
+/// assert_eq!(Ok("1,2".to_string()), positional.line(&[1, 2]));
+/// assert_eq!(vec!["value 1", "value 2"], positional.names());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataLine {
+    series: Vec<Series>,
+}
+
+impl DataLine {
+    /// Creates a line from the ordered `series`.
+    ///
+    /// Fails with an [`InvalidLabel`] when a label is empty or contains a label (`:`)
+    /// or values (`,`, ` `, `\t`) separator.
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{DataLine, InvalidLabel, Series};
+    ///
+    /// assert_eq!(
+    ///     Err(InvalidLabel("air temp".to_string())),
+    ///     DataLine::new([Series::labeled("air temp"), Series::Positional])
+    /// );
+    /// ```
+    pub fn new<I: IntoIterator<Item = Series>>(series: I) -> Result<Self, InvalidLabel> {
+        let series: Vec<Series> = series.into_iter().collect();
+
+        for series in &series {
+            if let Series::Labeled(label) = series {
+                if !is_valid_label(label) {
+                    return Err(InvalidLabel(label.clone()));
+                }
+            }
+        }
+
+        Ok(Self { series })
+    }
+
+    /// The series of the line in the order of the values.
+    pub fn series(&self) -> &[Series] {
+        &self.series
+    }
+
+    /// The names of the series as shown by the Arduino Serial Plotter UI.
+    pub fn names(&self) -> Vec<String> {
+        self.series
+            .iter()
+            .enumerate()
+            .map(|(index, series)| match series {
+                Series::Labeled(label) => label.clone(),
+                Series::Positional => format!("value {}", index + 1),
+            })
+            .collect()
+    }
+
+    /// Formats a frame without a line ending from the `values` given in the order of the series.
+    ///
+    /// # Formatting
+    ///
+    /// The values are formatted with their [`Display`](core::fmt::Display) implementation
+    /// which doesn't depend on the system locale, i.e. `f64`s always use a `.` decimal
+    /// separator, never a `,` which would split the value in two, and are never grouped
+    /// in thousands.
+    /// The shortest representation which parses back to the same `f64` is used.
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{DataLine, Series};
+    ///
+    /// let line = DataLine::new([
+    ///     Series::labeled("pi"),
+    ///     Series::labeled("zero"),
+    ///     Series::labeled("million"),
+    ///     Series::Positional,
+    /// ])
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     Ok("pi:3.14159,zero:-0,million:1000000,0.1".to_string()),
+    ///     line.line(&[3.14159, -0.0, 1000000.0, 0.1])
+    /// );
+    /// ```
+    pub fn line<V: core::fmt::Display>(&self, values: &[V]) -> Result<String, SchemaMismatch> {
+        if values.len() != self.series.len() {
+            return Err(SchemaMismatch::Count {
+                expected: self.series.len(),
+                found: values.len(),
+            });
+        }
+
+        let line = self
+            .series
+            .iter()
+            .zip(values)
+            .map(|(series, value)| match series {
+                Series::Labeled(label) => format!("{label}:{value}"),
+                Series::Positional => value.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Ok(line)
+    }
+}
+
+/// A single value of a frame (a single Data line) parsed with [`parse_variables`].
+///
+/// Formatting it with [`Display`](core::fmt::Display) produces the frame value back,
+/// e.g. `temp:23.5C`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    /// The label of the value, `None` for a positional (unlabeled) value.
+    pub label: Option<String>,
+    pub value: f64,
+    /// The unit suffix of the value, only parsed with [`ParseOptions::units`].
+    pub unit: Option<String>,
+}
+
+impl core::fmt::Display for Variable {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "{label}:")?;
+        }
+        write!(f, "{}", self.value)?;
+        if let Some(unit) = &self.unit {
+            f.write_str(unit)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A labeled numeric value of a [`NumericLine`], formatted as `label:value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledValue {
+    pub label: String,
+    pub value: f64,
+}
+
+impl LabeledValue {
+    /// Fails with an [`InvalidLabel`] when the label is empty or contains a label (`:`)
+    /// or values (`,`, ` `, `\t`) separator.
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{InvalidLabel, LabeledValue};
+    ///
+    /// assert_eq!("L1:1.5", LabeledValue::new("L1", 1.5).unwrap().to_string());
+    /// assert_eq!(
+    ///     Err(InvalidLabel("L1,L2".to_string())),
+    ///     LabeledValue::new("L1,L2", 1.5)
+    /// );
+    /// ```
+    pub fn new(label: impl Into<String>, value: f64) -> Result<Self, InvalidLabel> {
+        let label = label.into();
+        if !is_valid_label(&label) {
+            return Err(InvalidLabel(label));
+        }
+
+        Ok(Self { label, value })
+    }
+}
+
+impl core::fmt::Display for LabeledValue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{}", self.label, self.value)
+    }
+}
+
+/// A frame (a single Data line) of labeled numeric values, i.e. one value of every channel.
+///
+/// It's (de)serialized as the `label:value,label:value\n` string the Arduino Serial Plotter UI
+/// plots, so a `Data<NumericLine>` is a Data lines message, unlike a `Data<f64>`
+/// which is serialized as an array of numbers.
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::{Data, LabeledValue, NumericLine};
+///
+/// let data = Data(vec![
+///     NumericLine(vec![
+///         LabeledValue::new("L1", 1.5).unwrap(),
+///         LabeledValue::new("L2", 2.0).unwrap(),
+///     ]),
+///     NumericLine(vec![LabeledValue::new("L1", -3.0).unwrap()]),
+/// ]);
+///
+/// let json = serde_json::to_string(&data).unwrap();
+/// assert_eq!(r#"["L1:1.5,L2:2\n","L1:-3\n"]"#, json);
+/// assert_eq!(data, serde_json::from_str(&json).unwrap());
+///
+/// // positional values have no label
+/// assert!(serde_json::from_str::<Data<NumericLine>>(r#"["L1:1,2\n"]"#).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumericLine(pub Vec<LabeledValue>);
+
+impl core::fmt::Display for NumericLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (index, value) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{value}")?;
+        }
+
+        write!(f, "{}", EndOfLine::NewLine)
+    }
+}
+
+impl Serialize for NumericLine {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for NumericLine {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        let line = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+        let values = parse_variables(&line, ParseOptions::default())
+            .map_err(D::Error::custom)?
+            .into_iter()
+            .map(|variable| match variable.label {
+                Some(label) => Ok(LabeledValue {
+                    label,
+                    value: variable.value,
+                }),
+                None => Err(D::Error::custom(format!(
+                    "Positional value {} without a label",
+                    variable.value
+                ))),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self(values))
+    }
+}
+
+/// Options for [`parse_variables`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Extract a trailing unit from the values, e.g. `C` from `23.5C`.
+    ///
+    /// The Arduino Serial Plotter UI itself doesn't understand units,
+    /// so frames with units are useful only for analysis tooling.
+    pub units: bool,
+}
+
+/// Errors when parsing a frame with [`parse_variables`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseVariablesError {
+    /// The value is not a number, or a number followed by a unit with [`ParseOptions::units`].
+    #[error("Invalid value {0:?}")]
+    InvalidValue(String),
+}
+
+/// Parses the values of a frame (a single Data line), e.g. `temp:23.5,humidity:40`.
 ///
-/// <https://docs.arduino.cc/software/ide-v2/tutorials/ide-v2-serial-plotter>
+/// Values are separated by `,`, spaces or tabs, same as in the Arduino Serial Plotter UI,
+/// and a trailing line ending is ignored.
+///
+/// # Examples
 ///
 /// ```
-/// use arduino_plotter::protocol::Data;
+/// use arduino_plotter::protocol::{parse_variables, ParseOptions, ParseVariablesError, Variable};
 ///
-/// // data with no line ending
-/// let data = Data(vec![
-///     "L1:1,L2:2,L3:3,L4:4".to_string(),
-///     "Label_1:99,Label_2:98,Label_3:97,Label_4:96".to_string(),
-/// ]);
-/// let data_json = serde_json::json!([
-///     "L1:1,L2:2,L3:3,L4:4",
-///     "Label_1:99,Label_2:98,Label_3:97,Label_4:96"
-/// ]);
-/// let from_json = serde_json::from_value::<Data<String>>(data_json).expect("should be valid");
+/// let variables = parse_variables("temp:23.5,7\n", ParseOptions::default()).unwrap();
+/// assert_eq!(
+///     vec![
+///         Variable {
+///             label: Some("temp".to_string()),
+///             value: 23.5,
+///             unit: None
+///         },
+///         Variable {
+///             label: None,
+///             value: 7.0,
+///             unit: None
+///         },
+///     ],
+///     variables
+/// );
 ///
-/// assert_eq!(data, from_json);
+/// // units are not part of the plotter protocol
+/// assert_eq!(
+///     Err(ParseVariablesError::InvalidValue("23.5C".to_string())),
+///     parse_variables("temp:23.5C", ParseOptions::default())
+/// );
+///
+/// let with_units = ParseOptions { units: true };
+/// let variables = parse_variables("temp:23.5C,pressure:1.013e3hPa,count:3", with_units).unwrap();
+/// assert_eq!(Some("C"), variables[0].unit.as_deref());
+/// assert_eq!(1013.0, variables[1].value);
+/// assert_eq!(Some("hPa"), variables[1].unit.as_deref());
+/// assert_eq!(None, variables[2].unit);
+///
+/// // round-trips back to the frame
+/// let frame = variables
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect::<Vec<_>>()
+///     .join(",");
+/// assert_eq!("temp:23.5C,pressure:1013hPa,count:3", frame);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct Data<T: core::fmt::Display>(pub Vec<T>);
+pub fn parse_variables(
+    line: &str,
+    options: ParseOptions,
+) -> Result<Vec<Variable>, ParseVariablesError> {
+    split_fields(EndOfLine::strip(line))
+        .map(|variable| {
+            let (label, raw_value) = match variable.split_once(':') {
+                Some((label, value)) => (Some(label.to_string()), value),
+                None => (None, variable),
+            };
+            let invalid = || ParseVariablesError::InvalidValue(raw_value.to_string());
+
+            let (value, unit) = match raw_value.parse::<f64>() {
+                Ok(value) => (value, None),
+                Err(_) if options.units => {
+                    let (number, unit) = raw_value.split_at(number_len(raw_value));
+                    let value = number.parse::<f64>().map_err(|_| invalid())?;
+
+                    (value, Some(unit.to_string()))
+                }
+                Err(_) => return Err(invalid()),
+            };
+
+            Ok(Variable { label, value, unit })
+        })
+        .collect()
+}
+
+/// The length of the leading decimal number, e.g. `-1.5e3` of `-1.5e3hPa`.
+fn number_len(value: &str) -> usize {
+    let bytes = value.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    len += digits(len);
+    if bytes.get(len) == Some(&b'.') {
+        len += 1 + digits(len + 1);
+    }
+
+    // an exponent only when followed by digits, i.e. `e` of `1e` is a unit
+    if matches!(bytes.get(len), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(len + 1), Some(b'+' | b'-')));
+        let exponent_digits = digits(len + 1 + sign);
+        if exponent_digits > 0 {
+            len += 1 + sign + exponent_digits;
+        }
+    }
+
+    len
+}
 
 /// All the available Command names for both Client ([`ClientCommand`]) and Middleware ([`MiddlewareCommand`]).
-#[derive(Debug, Clone, Serialize, Deserialize, Display, FromStr)]
+///
+/// Newer webapp versions may introduce new commands, see
+/// [`ServerEvent::UnknownCommand`](crate::ServerEvent::UnknownCommand).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Display, FromStr)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[display(style = "SNAKE_CASE")]
+#[non_exhaustive]
 pub enum CommandName {
     /// Middleware Command (from WebSocket to Arduino Serial Plotter UI)
     OnSettingsDidChange,
@@ -51,13 +1169,138 @@ pub enum CommandName {
     ChangeSettings,
 }
 
+/// Returns the name and the data of a command object with a `command` which is
+/// not a known [`CommandName`], e.g. sent by a newer webapp version.
+pub(crate) fn unknown_command(payload: &str) -> Option<(String, serde_json::Value)> {
+    let mut json = serde_json::from_str::<serde_json::Value>(payload).ok()?;
+    let command = json.get("command")?;
+    if CommandName::deserialize(command).is_ok() {
+        return None;
+    }
+
+    let name = match command {
+        serde_json::Value::String(name) => name.clone(),
+        other => other.to_string(),
+    };
+    let data = json
+        .get_mut("data")
+        .map(serde_json::Value::take)
+        .unwrap_or_default();
+
+    Some((name, data))
+}
+
 /// Middleware Command (from WebSocket to Arduino Serial Plotter UI)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// # Examples
+///
+/// An `ON_SETTINGS_DID_CHANGE` message as sent by the Arduino IDE round-trips
+/// without changes:
+///
+/// ```
+/// use arduino_plotter::protocol::{EndOfLine, MiddlewareCommand};
+///
+/// let captured = concat!(
+///     r#"{"command":"ON_SETTINGS_DID_CHANGE","data":{"#,
+///     r#""pluggableMonitorSettings":{"baudrate":{"id":"baudrate","label":"Baudrate","type":"enum","#,
+///     r#""values":["300","1200","2400","4800","9600","19200","38400","57600","74880","115200","230400","250000","500000","1000000","2000000"],"#,
+///     r#""selectedValue":"9600"}},"#,
+///     r#""monitorUISettings":{"autoscroll":true,"timestamp":false,"lineEnding":"\r\n","interpolate":false,"#,
+///     r#""darkTheme":true,"wsPort":50001,"serialPort":"/dev/ttyACM0","connected":true,"generate":false}}}"#
+/// );
+///
+/// let command = serde_json::from_str::<MiddlewareCommand>(captured).unwrap();
+/// let ui_settings = command.0.monitor_ui_settings.as_ref().unwrap();
+/// assert_eq!(Some(EndOfLine::CarriageReturnNewLine), ui_settings.line_ending);
+/// assert_eq!(Some(50001), ui_settings.ws_port);
+/// assert_eq!(
+///     "9600",
+///     command.0.pluggable_monitor_settings.as_ref().unwrap()["baudrate"].selected_value
+/// );
+///
+/// assert_eq!(captured, serde_json::to_string(&command).unwrap());
+/// ```
+///
+/// The order of multiple pluggable monitor settings is not kept:
+///
+/// ```
+/// use arduino_plotter::protocol::MiddlewareCommand;
+///
+/// let captured = serde_json::json!({
+///     "command": "ON_SETTINGS_DID_CHANGE",
+///     "data": {
+///         "pluggableMonitorSettings": {
+///             "baudrate": { "id": "baudrate", "label": "Baudrate", "type": "enum", "values": ["9600", "115200"], "selectedValue": "115200" },
+///             "bits": { "id": "bits", "label": "Data bits", "type": "enum", "values": ["5", "6", "7", "8", "9"], "selectedValue": "8" },
+///             "parity": { "id": "parity", "label": "Parity", "type": "enum", "values": ["none", "even", "odd", "mark", "space"], "selectedValue": "none" },
+///             "stop_bits": { "id": "stop_bits", "label": "Stop bits", "type": "enum", "values": ["1", "1.5", "2"], "selectedValue": "1" }
+///         },
+///         "monitorUISettings": { "connected": false, "serialPort": "/dev/ttyUSB0", "generate": false }
+///     }
+/// });
+///
+/// let command = serde_json::from_value::<MiddlewareCommand>(captured.clone()).unwrap();
+/// assert_eq!(4, command.0.pluggable_monitor_settings.as_ref().unwrap().len());
+/// assert_eq!(captured, serde_json::to_value(&command).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(
     into = "Command<MonitorSettings>",
     try_from = "Command<MonitorSettings>"
 )]
 pub struct MiddlewareCommand(pub MonitorSettings);
+
+impl MiddlewareCommand {
+    /// The complete initial settings which should be sent to the Arduino Serial Plotter UI
+    /// right after it connects.
+    ///
+    /// Contains the `baudrate` [`PluggableMonitorSetting`] and all the [`MonitorModelState`]
+    /// settings, see [`MonitorSettings::into_full`], with the board `connected`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{Baudrate, EndOfLine, MiddlewareCommand};
+    ///
+    /// let initial = MiddlewareCommand::initial("/dev/ttyACM0", Baudrate::B9600, EndOfLine::NewLine, true);
+    ///
+    /// let ui_settings = initial.0.monitor_ui_settings.as_ref().unwrap();
+    /// assert_eq!(Some("/dev/ttyACM0"), ui_settings.serial_port.as_deref());
+    /// assert_eq!(Some(EndOfLine::NewLine), ui_settings.line_ending);
+    /// assert_eq!(Some(true), ui_settings.dark_theme);
+    /// assert_eq!(Some(true), ui_settings.connected);
+    /// assert_eq!(Some(true), ui_settings.autoscroll);
+    ///
+    /// let baudrate = &initial.0.pluggable_monitor_settings.as_ref().unwrap()["baudrate"];
+    /// assert_eq!(Some(Baudrate::B9600), baudrate.selected_baudrate());
+    /// ```
+    pub fn initial(
+        serial_port: impl Into<String>,
+        baudrate: Baudrate,
+        eol: EndOfLine,
+        dark_theme: bool,
+    ) -> Self {
+        let mut pluggable_monitor_settings = PluggableMonitorSettings::default();
+        pluggable_monitor_settings.insert(
+            PluggableMonitorSetting::BAUDRATE_ID.to_string(),
+            PluggableMonitorSetting::baudrate(baudrate),
+        );
+
+        let settings = MonitorSettings {
+            pluggable_monitor_settings: Some(pluggable_monitor_settings),
+            monitor_ui_settings: Some(MonitorModelState {
+                serial_port: Some(serial_port.into()),
+                line_ending: Some(eol),
+                dark_theme: Some(dark_theme),
+                connected: Some(true),
+                ..Default::default()
+            }),
+        };
+
+        Self(settings.into_full())
+    }
+}
+
 impl From<MiddlewareCommand> for Command<MonitorSettings> {
     fn from(value: MiddlewareCommand) -> Self {
         Self {
@@ -81,13 +1324,132 @@ impl TryFrom<Command<MonitorSettings>> for MiddlewareCommand {
 }
 
 /// Client Commands from Arduino Serial Plotter UI to WebSocket)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// # Examples
+///
+/// Hand-typed commands, e.g. when testing with `websocat`, parse the same way as
+/// the ones sent by the Arduino Serial Plotter UI:
+///
+/// ```
+/// use arduino_plotter::protocol::{ClientCommand, EndOfLine, MonitorModelState};
+///
+/// let json = r#"{"command":"CHANGE_SETTINGS","data":{"monitorUISettings":{"lineEnding":"\n"}}}"#;
+///
+/// match serde_json::from_str::<ClientCommand>(json).expect("Valid command") {
+///     ClientCommand::ChangeSettings(settings) => {
+///         assert!(settings.pluggable_monitor_settings.is_none());
+///         assert!(matches!(
+///             settings.monitor_ui_settings,
+///             Some(MonitorModelState {
+///                 line_ending: Some(EndOfLine::NewLine),
+///                 ..
+///             })
+///         ));
+///     }
+///     command => panic!("Unexpected command: {command:?}"),
+/// }
+///
+/// let json = r#"{"command":"SEND_MESSAGE","data":"hello\r\n"}"#;
+/// assert!(matches!(
+///     serde_json::from_str::<ClientCommand>(json).expect("Valid command"),
+///     ClientCommand::SendMessage(message) if message == "hello\r\n"
+/// ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "command", content = "data", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ClientCommand {
     SendMessage(String),
     ChangeSettings(MonitorSettings),
 }
 
+impl ClientCommand {
+    /// Returns the [`ClientCommand::SendMessage`] message terminated by exactly
+    /// the given [`EndOfLine`], ready to be written to the board.
+    ///
+    /// Any trailing line terminators already present in the message are stripped
+    /// with [`EndOfLine::strip`] first.
+    /// Returns `None` for all other commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{ClientCommand, EndOfLine, MonitorSettings};
+    ///
+    /// let command = ClientCommand::SendMessage("hello\r\n".to_string());
+    /// assert_eq!(
+    ///     Some("hello\r\n".to_string()),
+    ///     command.sanitized_message(EndOfLine::CarriageReturnNewLine)
+    /// );
+    /// assert_eq!(
+    ///     Some("hello\n".to_string()),
+    ///     command.sanitized_message(EndOfLine::NewLine)
+    /// );
+    /// assert_eq!(
+    ///     Some("hello".to_string()),
+    ///     command.sanitized_message(EndOfLine::NoLineEnding)
+    /// );
+    ///
+    /// let command = ClientCommand::ChangeSettings(MonitorSettings::default());
+    /// assert_eq!(None, command.sanitized_message(EndOfLine::NewLine));
+    /// ```
+    pub fn sanitized_message(&self, eol: EndOfLine) -> Option<String> {
+        match self {
+            ClientCommand::SendMessage(message) => {
+                Some(format!("{}{eol}", EndOfLine::strip(message)))
+            }
+            ClientCommand::ChangeSettings(_) => None,
+        }
+    }
+
+    /// Returns the [`ClientCommand::SendMessage`] message as sent by the UI,
+    /// including the line ending, and `None` for all other commands.
+    pub fn raw_message(&self) -> Option<&str> {
+        match self {
+            ClientCommand::SendMessage(message) => Some(message),
+            ClientCommand::ChangeSettings(_) => None,
+        }
+    }
+
+    /// Returns the [`ClientCommand::SendMessage`] message without the trailing
+    /// `eol` appended by the UI, i.e. the line ending of [`MonitorModelState::line_ending`].
+    ///
+    /// Only a single trailing `eol` is stripped, see [`EndOfLine::split`] for messages
+    /// with multiple lines.
+    /// Returns `None` for all other commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{ClientCommand, EndOfLine, MonitorSettings};
+    ///
+    /// let command = ClientCommand::SendMessage("hello\r\n".to_string());
+    /// assert_eq!(
+    ///     Some("hello"),
+    ///     command.message_without_eol(EndOfLine::CarriageReturnNewLine)
+    /// );
+    /// // a different line ending is kept
+    /// assert_eq!(
+    ///     Some("hello\r\n"),
+    ///     command.message_without_eol(EndOfLine::CarriageReturn)
+    /// );
+    /// assert_eq!(
+    ///     Some("hello\r\n"),
+    ///     command.message_without_eol(EndOfLine::NoLineEnding)
+    /// );
+    /// assert_eq!(Some("hello\r\n"), command.raw_message());
+    ///
+    /// let command = ClientCommand::ChangeSettings(MonitorSettings::default());
+    /// assert_eq!(None, command.message_without_eol(EndOfLine::NewLine));
+    /// assert_eq!(None, command.raw_message());
+    /// ```
+    pub fn message_without_eol(&self, eol: EndOfLine) -> Option<&str> {
+        let message = self.raw_message()?;
+        let eol = eol.to_string();
+
+        Some(message.strip_suffix(eol.as_str()).unwrap_or(message))
+    }
+}
+
 impl From<ClientCommand> for Command<serde_json::Value> {
     fn from(value: ClientCommand) -> Self {
         match value {
@@ -120,20 +1482,409 @@ pub struct PluggableMonitorSetting {
     pub id: Option<String>,
     /// A human-readable label of the setting (to be displayed on the GUI), e.g. `"Baudrate"`
     pub label: Option<String>,
-    /// The setting type (at the moment only "enum" is available)
+    /// The setting type, e.g. "enum", see [`LabelType`]
     pub r#type: Option<LabelType>,
-    /// The values allowed on "enum" types, e.g. `vec!["300".to_string(), "9600".into(), "115200".into()]`
+    /// The values allowed on "enum" types, e.g. `vec!["300".into(), "9600".into(), "115200".into()]`
     #[serde(default)]
-    pub values: Vec<String>,
+    pub values: Vec<SettingValue>,
     /// The selected value, e.g. `"9600"`
     pub selected_value: String,
 }
 
-/// The Pluggable Monitor setting type.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum LabelType {
-    Enum,
+/// A value allowed by an "enum" [`PluggableMonitorSetting`], optionally with
+/// a human-readable label.
+///
+/// It's deserialized from both a plain string, e.g. `"115200"`, and an object,
+/// e.g. `{"label": "Fast", "value": "115200"}`, sent by richer webapp versions.
+/// A value without a label is serialized as a plain string, which all the webapp versions
+/// understand.
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::SettingValue;
+///
+/// let values = serde_json::from_str::<Vec<SettingValue>>(
+///     r#"["9600", {"label": "Fast", "value": "115200"}, {"value": "230400"}]"#,
+/// )
+/// .expect("Valid values");
+///
+/// assert_eq!(
+///     vec![
+///         SettingValue::from("9600"),
+///         SettingValue::labeled("Fast", "115200"),
+///         SettingValue::from("230400"),
+///     ],
+///     values
+/// );
+///
+/// assert_eq!(
+///     r#"["9600",{"label":"Fast","value":"115200"},"230400"]"#,
+///     serde_json::to_string(&values).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "RawSettingValue", into = "RawSettingValue")]
+pub struct SettingValue {
+    /// The label to be displayed on the GUI instead of the value, e.g. `"Fast"`
+    pub label: Option<String>,
+    /// The value, e.g. `"115200"`
+    pub value: String,
+}
+
+impl SettingValue {
+    /// Creates a value with a human-readable label.
+    pub fn labeled(label: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            label: Some(label.into()),
+            value: value.into(),
+        }
+    }
+}
+
+impl From<String> for SettingValue {
+    fn from(value: String) -> Self {
+        Self { label: None, value }
+    }
+}
+
+impl From<&str> for SettingValue {
+    fn from(value: &str) -> Self {
+        Self::from(value.to_string())
+    }
+}
+
+impl core::fmt::Display for SettingValue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// Both JSON shapes of a [`SettingValue`].
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawSettingValue {
+    Flat(String),
+    Labeled {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        value: String,
+    },
+}
+
+impl From<RawSettingValue> for SettingValue {
+    fn from(raw: RawSettingValue) -> Self {
+        match raw {
+            RawSettingValue::Flat(value) => Self { label: None, value },
+            RawSettingValue::Labeled { label, value } => Self { label, value },
+        }
+    }
+}
+
+impl From<SettingValue> for RawSettingValue {
+    fn from(value: SettingValue) -> Self {
+        match value.label {
+            None => Self::Flat(value.value),
+            label => Self::Labeled {
+                label,
+                value: value.value,
+            },
+        }
+    }
+}
+
+/// The Pluggable Monitor setting type.
+///
+/// The pluggable monitor specification only defines `"enum"` at the moment,
+/// `"number"` and `"boolean"` are reported by newer monitors and any other type
+/// round-trips as [`LabelType::Unknown`] instead of failing the whole settings message.
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::{LabelType, PluggableMonitorSettings};
+///
+/// let json = serde_json::json!({
+///     "baudrate": {
+///         "id": "baudrate",
+///         "type": "enum",
+///         "values": ["9600", "115200"],
+///         "selectedValue": "9600"
+///     },
+///     "timeout": {
+///         "id": "timeout",
+///         "type": "duration",
+///         "selectedValue": "5s"
+///     }
+/// });
+///
+/// let settings = serde_json::from_value::<PluggableMonitorSettings>(json.clone())
+///     .expect("Valid PluggableMonitorSettings");
+/// assert_eq!(Some(LabelType::Enum), settings["baudrate"].r#type);
+/// assert_eq!(
+///     Some(LabelType::Unknown("duration".to_string())),
+///     settings["timeout"].r#type
+/// );
+///
+/// let round_trip = serde_json::to_value(&settings).unwrap();
+/// assert_eq!("duration", round_trip["timeout"]["type"]);
+/// assert_eq!("\"number\"", serde_json::to_string(&LabelType::Number).unwrap());
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum LabelType {
+    /// One of the [`PluggableMonitorSetting::values`].
+    Enum,
+    Number,
+    Boolean,
+    /// A type which is not known by this crate.
+    Unknown(String),
+}
+
+impl From<String> for LabelType {
+    fn from(label_type: String) -> Self {
+        match label_type.as_str() {
+            "enum" => Self::Enum,
+            "number" => Self::Number,
+            "boolean" => Self::Boolean,
+            _ => Self::Unknown(label_type),
+        }
+    }
+}
+
+impl From<LabelType> for String {
+    fn from(label_type: LabelType) -> Self {
+        match label_type {
+            LabelType::Enum => "enum".to_string(),
+            LabelType::Number => "number".to_string(),
+            LabelType::Boolean => "boolean".to_string(),
+            LabelType::Unknown(label_type) => label_type,
+        }
+    }
+}
+
+impl PluggableMonitorSetting {
+    /// The identifier of the baudrate setting.
+    pub const BAUDRATE_ID: &'static str = "baudrate";
+
+    /// Creates the `baudrate` enum setting with all the [`Baudrate::STANDARD`] values
+    /// and the given selected value.
+    ///
+    /// A custom (non-standard) selected baudrate is added to the allowed values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{Baudrate, PluggableMonitorSetting};
+    ///
+    /// let setting = PluggableMonitorSetting::baudrate(Baudrate::B9600);
+    /// assert_eq!(Some("baudrate"), setting.id.as_deref());
+    /// assert_eq!("9600", setting.selected_value);
+    /// assert_eq!(Some(Baudrate::B9600), setting.selected_baudrate());
+    ///
+    /// let custom = PluggableMonitorSetting::baudrate(Baudrate(12_345));
+    /// assert!(custom.values.contains(&"12345".into()));
+    /// ```
+    pub fn baudrate(selected: Baudrate) -> Self {
+        let mut values = Baudrate::STANDARD
+            .iter()
+            .map(|baudrate| SettingValue::from(baudrate.to_string()))
+            .collect::<Vec<_>>();
+        if !selected.is_standard() {
+            values.push(selected.to_string().into());
+        }
+
+        Self {
+            id: Some(Self::BAUDRATE_ID.to_string()),
+            label: Some("Baudrate".to_string()),
+            r#type: Some(LabelType::Enum),
+            values,
+            selected_value: selected.to_string(),
+        }
+    }
+
+    /// Creates an enum setting (a dropdown in the UI) with the allowed `values`
+    /// and the `selected` one.
+    ///
+    /// Fails when the `selected` value is not one of the allowed `values`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{
+    ///     LabelType, NotAllowedValue, PluggableMonitorSetting, SettingValue,
+    /// };
+    ///
+    /// let parity = PluggableMonitorSetting::new_enum(
+    ///     "parity",
+    ///     "Parity",
+    ///     ["none", "even", "odd"],
+    ///     "none",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(Some(LabelType::Enum), parity.r#type);
+    /// assert_eq!("none", parity.selected());
+    /// assert_eq!(3, parity.values().len());
+    ///
+    /// let speed = PluggableMonitorSetting::new_enum(
+    ///     "speed",
+    ///     "Speed",
+    ///     [SettingValue::labeled("Slow", "9600"), SettingValue::labeled("Fast", "115200")],
+    ///     "115200",
+    /// );
+    /// assert!(speed.is_ok());
+    ///
+    /// assert_eq!(
+    ///     Err(NotAllowedValue("mark".to_string())),
+    ///     PluggableMonitorSetting::new_enum("parity", "Parity", ["none"], "mark")
+    /// );
+    /// ```
+    pub fn new_enum<V: Into<SettingValue>>(
+        id: impl Into<String>,
+        label: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+        selected: impl Into<String>,
+    ) -> Result<Self, NotAllowedValue> {
+        let values = values.into_iter().map(Into::into).collect::<Vec<_>>();
+        let selected = selected.into();
+
+        if !values.iter().any(|value| value.value == selected) {
+            return Err(NotAllowedValue(selected));
+        }
+
+        Ok(Self {
+            id: Some(id.into()),
+            label: Some(label.into()),
+            r#type: Some(LabelType::Enum),
+            values,
+            selected_value: selected,
+        })
+    }
+
+    /// The selected value.
+    pub fn selected(&self) -> &str {
+        &self.selected_value
+    }
+
+    /// The allowed values.
+    pub fn values(&self) -> &[SettingValue] {
+        &self.values
+    }
+
+    /// Parses the selected value as a [`Baudrate`].
+    pub fn selected_baudrate(&self) -> Option<Baudrate> {
+        self.selected_value.parse().ok()
+    }
+}
+
+/// The selected value of an enum [`PluggableMonitorSetting`] is not one of its allowed values,
+/// see [`PluggableMonitorSetting::new_enum`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Selected value {0:?} is not one of the allowed values")]
+pub struct NotAllowedValue(pub String);
+
+/// A serial connection baudrate.
+///
+/// All the baudrates used by the Arduino IDE are available as constants,
+/// e.g. [`Baudrate::B9600`], but custom baudrates are supported as well.
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::Baudrate;
+///
+/// assert_eq!("115200", Baudrate::B115200.to_string());
+/// assert_eq!(Ok(Baudrate::B9600), "9600".parse());
+///
+/// let custom = "12345".parse::<Baudrate>().expect("Valid baudrate");
+/// assert_eq!(Baudrate(12_345), custom);
+/// assert!(!custom.is_standard());
+///
+/// assert_eq!("\"9600\"", serde_json::to_string(&Baudrate::B9600).unwrap());
+/// assert!("fast".parse::<Baudrate>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Baudrate(pub u32);
+
+impl Baudrate {
+    pub const B300: Self = Self(300);
+    pub const B600: Self = Self(600);
+    pub const B750: Self = Self(750);
+    pub const B1200: Self = Self(1_200);
+    pub const B2400: Self = Self(2_400);
+    pub const B4800: Self = Self(4_800);
+    pub const B9600: Self = Self(9_600);
+    pub const B19200: Self = Self(19_200);
+    pub const B31250: Self = Self(31_250);
+    pub const B38400: Self = Self(38_400);
+    pub const B57600: Self = Self(57_600);
+    pub const B74880: Self = Self(74_880);
+    pub const B115200: Self = Self(115_200);
+    pub const B230400: Self = Self(230_400);
+    pub const B250000: Self = Self(250_000);
+    pub const B460800: Self = Self(460_800);
+    pub const B500000: Self = Self(500_000);
+    pub const B921600: Self = Self(921_600);
+    pub const B1000000: Self = Self(1_000_000);
+    pub const B2000000: Self = Self(2_000_000);
+
+    /// All the standard baudrates offered by the Arduino IDE.
+    pub const STANDARD: &'static [Self] = &[
+        Self::B300,
+        Self::B600,
+        Self::B750,
+        Self::B1200,
+        Self::B2400,
+        Self::B4800,
+        Self::B9600,
+        Self::B19200,
+        Self::B31250,
+        Self::B38400,
+        Self::B57600,
+        Self::B74880,
+        Self::B115200,
+        Self::B230400,
+        Self::B250000,
+        Self::B460800,
+        Self::B500000,
+        Self::B921600,
+        Self::B1000000,
+        Self::B2000000,
+    ];
+
+    /// Whether the baudrate is one of the [`Baudrate::STANDARD`] ones.
+    pub fn is_standard(&self) -> bool {
+        Self::STANDARD.contains(self)
+    }
+}
+
+impl core::fmt::Display for Baudrate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl core::str::FromStr for Baudrate {
+    type Err = core::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl From<Baudrate> for String {
+    fn from(baudrate: Baudrate) -> Self {
+        baudrate.to_string()
+    }
+}
+
+impl TryFrom<String> for Baudrate {
+    type Error = core::num::ParseIntError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
 }
 
 /// All the Pluggable Monitor settings, i.e. a connected serial device,
@@ -165,7 +1916,7 @@ pub enum LabelType {
 /// assert!(settings.contains_key("baudrate"));
 /// assert!(settings.contains_key("otherSetting"));
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(transparent)]
 pub struct PluggableMonitorSettings(pub HashMap<String, PluggableMonitorSetting>);
 
@@ -227,7 +1978,7 @@ impl DerefMut for PluggableMonitorSettings {
 /// );
 /// assert_eq!("\r\n", &carriage_return_new_line.to_string());
 /// ```
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Display, FromStr)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display, FromStr)]
 pub enum EndOfLine {
     #[display("")]
     #[serde(rename = "")]
@@ -263,30 +2014,224 @@ impl EndOfLine {
     pub const EOL: &'static [&'static str] = &["", "\n", "\r", "\r\n"];
 
     /// Whether a string contains any of the EndOfLine values inside of it.
-    pub fn contains_eol(string: String) -> bool {
-        Self::EOL.iter().any(|eol| string.contains(eol))
+    ///
+    /// Only the actual line terminators (`\n`, `\r` and `\r\n`) are looked for,
+    /// the empty [`EndOfLine::NoLineEnding`] is not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::EndOfLine;
+    ///
+    /// assert!(!EndOfLine::contains_eol("no newline here"));
+    /// assert!(EndOfLine::contains_eol("new\nline"));
+    /// assert!(EndOfLine::contains_eol("carriage return\r"));
+    /// ```
+    pub fn contains_eol(string: &str) -> bool {
+        Self::EOL
+            .iter()
+            .filter(|eol| !eol.is_empty())
+            .any(|eol| string.contains(eol))
+    }
+
+    /// Strips all trailing line terminators (`\n`, `\r` and `\r\n`) from the end of the string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::EndOfLine;
+    ///
+    /// assert_eq!("message", EndOfLine::strip("message"));
+    /// assert_eq!("message", EndOfLine::strip("message\n"));
+    /// assert_eq!("message", EndOfLine::strip("message\r\n\r\n"));
+    /// assert_eq!("multi\nline", EndOfLine::strip("multi\nline\r"));
+    /// ```
+    pub fn strip(string: &str) -> &str {
+        string.trim_end_matches(['\r', '\n'])
+    }
+
+    /// Splits the `input` into lines terminated by this line ending, without the terminators.
+    ///
+    /// A trailing line ending doesn't yield an empty last line and the other line endings
+    /// are kept as part of the lines.
+    /// [`EndOfLine::NoLineEnding`] yields the whole `input` as a single line.
+    /// An empty `input` yields no lines with any line ending.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::EndOfLine;
+    ///
+    /// let lines = |eol: EndOfLine, input| eol.split(input).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["L1:1", "L1:2"], lines(EndOfLine::NewLine, "L1:1\nL1:2\n"));
+    /// assert_eq!(vec!["L1:1", "L1:2"], lines(EndOfLine::NewLine, "L1:1\nL1:2"));
+    /// // no stray `\r` with a `\r\n` line ending
+    /// assert_eq!(
+    ///     vec!["L1:1", "", "L1:2"],
+    ///     lines(EndOfLine::CarriageReturnNewLine, "L1:1\r\n\r\nL1:2\r\n")
+    /// );
+    /// assert_eq!(vec!["L1:1", "L1:2"], lines(EndOfLine::CarriageReturn, "L1:1\rL1:2\r"));
+    ///
+    /// // mixed line endings, only the configured one splits the lines
+    /// assert_eq!(
+    ///     vec!["L1:1\nL1:2", "L1:3\n"],
+    ///     lines(EndOfLine::CarriageReturnNewLine, "L1:1\nL1:2\r\nL1:3\n")
+    /// );
+    /// assert_eq!(
+    ///     vec!["L1:1\r", "L1:2"],
+    ///     lines(EndOfLine::NewLine, "L1:1\r\nL1:2\n")
+    /// );
+    ///
+    /// assert_eq!(vec!["L1:1\nL1:2\n"], lines(EndOfLine::NoLineEnding, "L1:1\nL1:2\n"));
+    /// assert!(lines(EndOfLine::NewLine, "").is_empty());
+    /// assert!(lines(EndOfLine::NoLineEnding, "").is_empty());
+    /// ```
+    pub fn split<'a>(&self, input: &'a str) -> impl Iterator<Item = &'a str> {
+        let terminator = match self {
+            Self::NoLineEnding => None,
+            Self::NewLine => Some("\n"),
+            Self::CarriageReturn => Some("\r"),
+            Self::CarriageReturnNewLine => Some("\r\n"),
+        };
+
+        let whole = (terminator.is_none() && !input.is_empty()).then_some(input);
+        let lines = terminator.map(|terminator| input.split_terminator(terminator));
+
+        whole.into_iter().chain(lines.into_iter().flatten())
     }
 }
 
+/// Formats the `time_of_day` (the time since midnight) as the timestamp prefix shown by
+/// the Arduino IDE serial monitor when [`MonitorModelState::timestamp`] is enabled,
+/// i.e. `HH:mm:ss.SSS -> `.
+///
+/// Neither the Arduino Serial Plotter UI nor the serial monitor has a setting for
+/// the timestamp format, so this is the only format consistent with the UI.
+/// A `time_of_day` of more than a day wraps around.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use arduino_plotter::protocol::{timestamp_prefix, MonitorModelState};
+///
+/// assert_eq!(
+///     "13:05:09.042 -> ",
+///     timestamp_prefix(Duration::from_millis(((13 * 60 + 5) * 60 + 9) * 1000 + 42))
+/// );
+/// assert_eq!("00:00:00.000 -> ", timestamp_prefix(Duration::from_secs(24 * 60 * 60)));
+///
+/// // only the flag is serialized, there's no format setting
+/// let settings = MonitorModelState {
+///     timestamp: Some(true),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     serde_json::json!({ "timestamp": true }),
+///     serde_json::to_value(&settings).unwrap()
+/// );
+/// ```
+pub fn timestamp_prefix(time_of_day: core::time::Duration) -> String {
+    let millis = time_of_day.as_millis() % (24 * 60 * 60 * 1000);
+    let (hours, minutes, seconds, millis) = (
+        millis / (60 * 60 * 1000),
+        millis / (60 * 1000) % 60,
+        millis / 1000 % 60,
+        millis % 1000,
+    );
+
+    format!("{hours:02}:{minutes:02}:{seconds:02}.{millis:03} -> ")
+}
+
 /// All the UI Monitor settings that can be changed in the Arduino serial
 /// plotter application.
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+///
+/// Some versions of the application send the boolean settings as `"true"` and `"false"`
+/// strings, both forms are accepted while serializing always emits JSON booleans.
+///
+/// The number of points the plot retains (the X axis window) is not part of the settings,
+/// the Arduino Serial Plotter UI manages it on its own and it can't be requested by the middleware.
+/// The same goes for the colors and the legend of the series, they are assigned by
+/// the UI from its fixed palette in the order the labels are first received.
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::MonitorModelState;
+///
+/// let booleans = serde_json::json!({
+///     "autoscroll": true,
+///     "timestamp": false,
+///     "darkTheme": true,
+///     "connected": true,
+///     "generate": false
+/// });
+/// let strings = serde_json::json!({
+///     "autoscroll": "true",
+///     "timestamp": "false",
+///     "darkTheme": "true",
+///     "connected": "true",
+///     "generate": "false"
+/// });
+///
+/// let from_booleans = serde_json::from_value::<MonitorModelState>(booleans.clone()).unwrap();
+/// let from_strings = serde_json::from_value::<MonitorModelState>(strings).unwrap();
+///
+/// assert_eq!(Some(true), from_strings.dark_theme);
+/// assert_eq!(from_booleans, from_strings);
+/// assert_eq!(booleans, serde_json::to_value(&from_strings).unwrap());
+///
+/// assert!(
+///     serde_json::from_value::<MonitorModelState>(serde_json::json!({ "darkTheme": "yes" }))
+///         .is_err()
+/// );
+///
+/// // fields which are not settings, e.g. a window size, are ignored
+/// let window = serde_json::json!({ "darkTheme": true, "points": 50 });
+/// assert_eq!(
+///     serde_json::json!({ "darkTheme": true }),
+///     serde_json::to_value(serde_json::from_value::<MonitorModelState>(window).unwrap())
+///         .unwrap()
+/// );
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MonitorModelState {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient_bool::option",
+        skip_serializing_if = "Option::is_none"
+    )]
     /// Used by the serial monitors to stick at the bottom of the window.
     pub autoscroll: Option<bool>,
     /// Enable timestamp next to the actual data used by the serial monitors.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    ///
+    /// The timestamp format is fixed by the serial monitors and it's not a setting,
+    /// see [`timestamp_prefix`].
+    #[serde(
+        default,
+        deserialize_with = "lenient_bool::option",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub timestamp: Option<bool>,
     /// Clients store the information about the last EOL used when sending a message to the board.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line_ending: Option<EndOfLine>,
     /// Enables interpolation of the chart in the Serial Plotter App.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient_bool::option",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub interpolate: Option<bool>,
     // Whether to enable Dark theme or stick to the Light theme.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient_bool::option",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub dark_theme: Option<bool>,
     /// the current websocket port where the communication happens.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -295,18 +2240,58 @@ pub struct MonitorModelState {
     /// e.g. `/dev/ttyACM0` (linux), `/dev/ttyUSB0` (linux), etc.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub serial_port: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient_bool::option",
+        skip_serializing_if = "Option::is_none"
+    )]
     /// The connection status of the pluggable monitor to the actual board.
     pub connected: Option<bool>,
     /// Enable mocked data generation.
-    #[serde(default)]
-    pub generate: bool,
+    #[serde(
+        default,
+        deserialize_with = "lenient_bool::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub generate: Option<bool>,
+}
+
+/// Deserializes booleans sent either as JSON booleans or as `"true"`/`"false"` strings.
+mod lenient_bool {
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LenientBool {
+        Bool(bool),
+        String(String),
+    }
+
+    impl LenientBool {
+        fn into_bool<E: Error>(self) -> Result<bool, E> {
+            match self {
+                Self::Bool(value) => Ok(value),
+                Self::String(value) if value == "true" => Ok(true),
+                Self::String(value) if value == "false" => Ok(false),
+                Self::String(other) => Err(E::invalid_value(
+                    serde::de::Unexpected::Str(&other),
+                    &"a boolean or a \"true\"/\"false\" string",
+                )),
+            }
+        }
+    }
+
+    pub fn option<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+        Option::<LenientBool>::deserialize(deserializer)?
+            .map(LenientBool::into_bool)
+            .transpose()
+    }
 }
 
 /// The [`MiddlewareCommand`] Monitor settings that are sent to the
 /// Arduino serial plotter UI.
 /// This contains both [`PluggableMonitorSettings`] and [`MonitorModelState`].
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MonitorSettings {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -318,3 +2303,516 @@ pub struct MonitorSettings {
     )]
     pub monitor_ui_settings: Option<MonitorModelState>,
 }
+
+impl MonitorSettings {
+    /// Returns a [`MonitorSettingsBuilder`].
+    pub fn builder() -> MonitorSettingsBuilder {
+        MonitorSettingsBuilder::new()
+    }
+
+    /// Fills in all the missing settings which the Arduino Serial Plotter UI
+    /// needs on its initial render.
+    ///
+    /// Missing [`MonitorModelState`] fields are set to the UI defaults and
+    /// missing [`PluggableMonitorSettings`] are set to an empty map.
+    /// `ws_port` and `serial_port` are left untouched as the crate can't know them
+    /// and changing `ws_port` will close the current connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{EndOfLine, MonitorModelState, MonitorSettings};
+    ///
+    /// let settings = MonitorSettings {
+    ///     monitor_ui_settings: Some(MonitorModelState {
+    ///         dark_theme: Some(true),
+    ///         ..Default::default()
+    ///     }),
+    ///     ..Default::default()
+    /// }
+    /// .into_full();
+    ///
+    /// let json = serde_json::to_value(&settings).unwrap();
+    /// assert_eq!(
+    ///     serde_json::json!({
+    ///         "pluggableMonitorSettings": {},
+    ///         "monitorUISettings": {
+    ///             "autoscroll": true,
+    ///             "timestamp": false,
+    ///             "lineEnding": "\n",
+    ///             "interpolate": false,
+    ///             "darkTheme": true,
+    ///             "connected": false,
+    ///             "generate": false,
+    ///         }
+    ///     }),
+    ///     json
+    /// );
+    /// ```
+    pub fn into_full(self) -> Self {
+        let ui_settings = self.monitor_ui_settings.unwrap_or_default();
+
+        Self {
+            pluggable_monitor_settings: Some(self.pluggable_monitor_settings.unwrap_or_default()),
+            monitor_ui_settings: Some(MonitorModelState {
+                autoscroll: Some(ui_settings.autoscroll.unwrap_or(true)),
+                timestamp: Some(ui_settings.timestamp.unwrap_or(false)),
+                line_ending: Some(ui_settings.line_ending.unwrap_or(EndOfLine::NewLine)),
+                interpolate: Some(ui_settings.interpolate.unwrap_or(false)),
+                dark_theme: Some(ui_settings.dark_theme.unwrap_or(false)),
+                connected: Some(ui_settings.connected.unwrap_or(false)),
+                generate: Some(ui_settings.generate.unwrap_or(false)),
+                ..ui_settings
+            }),
+        }
+    }
+
+    /// Merges the `newer` settings on top of these ones.
+    ///
+    /// Every setting present in `newer` overrides the current one while the missing
+    /// ones are kept, pluggable monitor settings are merged by their id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{MonitorModelState, MonitorSettings};
+    ///
+    /// let ui_settings = |ui_settings| MonitorSettings {
+    ///     monitor_ui_settings: Some(ui_settings),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let current = ui_settings(MonitorModelState {
+    ///     dark_theme: Some(true),
+    ///     interpolate: Some(false),
+    ///     ..Default::default()
+    /// });
+    /// let newer = ui_settings(MonitorModelState {
+    ///     interpolate: Some(true),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let expected = ui_settings(MonitorModelState {
+    ///     dark_theme: Some(true),
+    ///     interpolate: Some(true),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(expected, current.merge(newer));
+    ///
+    /// // the mocked data generation can be switched off again
+    /// let generate = MonitorSettings::builder().generate(true).build();
+    /// let stop = MonitorSettings::builder().generate(false).build();
+    /// assert_eq!(stop.clone(), generate.merge(stop));
+    /// ```
+    pub fn merge(mut self, newer: Self) -> Self {
+        self.apply(&newer);
+
+        self
+    }
+
+    /// Applies a partial `update`, e.g. of a received `CHANGE_SETTINGS` command,
+    /// on top of the current settings in place, see [`MonitorSettings::merge`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{EndOfLine, MonitorSettings};
+    ///
+    /// let mut current = MonitorSettings::builder()
+    ///     .dark_theme(true)
+    ///     .line_ending(EndOfLine::NewLine)
+    ///     .build()
+    ///     .into_full();
+    ///
+    /// let update = MonitorSettings::builder()
+    ///     .line_ending(EndOfLine::CarriageReturnNewLine)
+    ///     .build();
+    /// current.apply(&update);
+    ///
+    /// let ui_settings = current.monitor_ui_settings.unwrap();
+    /// assert_eq!(Some(true), ui_settings.dark_theme);
+    /// assert_eq!(Some(EndOfLine::CarriageReturnNewLine), ui_settings.line_ending);
+    /// assert_eq!(Some(true), ui_settings.autoscroll);
+    /// ```
+    pub fn apply(&mut self, update: &MonitorSettings) {
+        fn update_setting<T: Clone>(current: &mut Option<T>, newer: &Option<T>) {
+            if newer.is_some() {
+                current.clone_from(newer);
+            }
+        }
+
+        if let Some(newer) = &update.pluggable_monitor_settings {
+            self.pluggable_monitor_settings
+                .get_or_insert_with(Default::default)
+                .extend(
+                    newer
+                        .iter()
+                        .map(|(id, setting)| (id.clone(), setting.clone())),
+                );
+        }
+
+        if let Some(newer) = &update.monitor_ui_settings {
+            let current = self
+                .monitor_ui_settings
+                .get_or_insert_with(Default::default);
+
+            update_setting(&mut current.autoscroll, &newer.autoscroll);
+            update_setting(&mut current.timestamp, &newer.timestamp);
+            update_setting(&mut current.line_ending, &newer.line_ending);
+            update_setting(&mut current.interpolate, &newer.interpolate);
+            update_setting(&mut current.dark_theme, &newer.dark_theme);
+            update_setting(&mut current.ws_port, &newer.ws_port);
+            update_setting(&mut current.serial_port, &newer.serial_port);
+            update_setting(&mut current.connected, &newer.connected);
+            update_setting(&mut current.generate, &newer.generate);
+        }
+    }
+
+    /// Whether any of the [`MonitorModelState`] settings is present, i.e. the UI state
+    /// should be updated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::MonitorSettings;
+    ///
+    /// // only the baudrate has changed
+    /// let baudrate = serde_json::from_value::<MonitorSettings>(serde_json::json!({
+    ///     "pluggableMonitorSettings": {
+    ///         "baudrate": { "id": "baudrate", "selectedValue": "115200" }
+    ///     }
+    /// }))
+    /// .unwrap();
+    /// assert!(baudrate.monitor_ui_settings.is_none());
+    /// assert!(baudrate.has_pluggable_changes());
+    /// assert!(!baudrate.has_ui_changes());
+    ///
+    /// let dark_theme = serde_json::from_value::<MonitorSettings>(serde_json::json!({
+    ///     "monitorUISettings": { "darkTheme": true },
+    ///     "pluggableMonitorSettings": {}
+    /// }))
+    /// .unwrap();
+    /// assert!(dark_theme.has_ui_changes());
+    /// assert!(!dark_theme.has_pluggable_changes());
+    /// ```
+    pub fn has_ui_changes(&self) -> bool {
+        self.monitor_ui_settings
+            .as_ref()
+            .is_some_and(|ui_settings| ui_settings != &MonitorModelState::default())
+    }
+
+    /// Whether any of the [`PluggableMonitorSettings`] is present, i.e. the serial
+    /// connection should be reconfigured.
+    ///
+    /// See [`MonitorSettings::has_ui_changes`].
+    pub fn has_pluggable_changes(&self) -> bool {
+        self.pluggable_monitor_settings
+            .as_ref()
+            .is_some_and(|pluggable_settings| !pluggable_settings.is_empty())
+    }
+
+    /// Guesses the [`SchemaVersion`] targeted by the settings based on which
+    /// fields are present.
+    ///
+    /// Returns `None` when there are no fields which can tell the versions apart,
+    /// e.g. when only [`PluggableMonitorSettings`] are present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arduino_plotter::protocol::{MonitorSettings, SchemaVersion};
+    ///
+    /// let serial_monitor = serde_json::from_value::<MonitorSettings>(serde_json::json!({
+    ///     "monitorUISettings": { "lineEnding": "\n", "autoscroll": true }
+    /// }))
+    /// .unwrap();
+    /// assert_eq!(Some(SchemaVersion::V1), serial_monitor.detect_version());
+    ///
+    /// let serial_plotter = serde_json::from_value::<MonitorSettings>(serde_json::json!({
+    ///     "monitorUISettings": { "lineEnding": "\n", "darkTheme": true }
+    /// }))
+    /// .unwrap();
+    /// assert_eq!(Some(SchemaVersion::V2), serial_plotter.detect_version());
+    ///
+    /// assert_eq!(None, MonitorSettings::default().detect_version());
+    /// ```
+    pub fn detect_version(&self) -> Option<SchemaVersion> {
+        let ui_settings = self.monitor_ui_settings.as_ref()?;
+
+        let v2 = ui_settings.interpolate.is_some()
+            || ui_settings.dark_theme.is_some()
+            || ui_settings.ws_port.is_some()
+            || ui_settings.serial_port.is_some()
+            || ui_settings.connected.is_some()
+            || ui_settings.generate.is_some();
+        let v1 = ui_settings.autoscroll.is_some()
+            || ui_settings.timestamp.is_some()
+            || ui_settings.line_ending.is_some();
+
+        if v2 {
+            Some(SchemaVersion::V2)
+        } else if v1 {
+            Some(SchemaVersion::V1)
+        } else {
+            None
+        }
+    }
+}
+
+/// Builds [`MonitorSettings`] without spelling out the nested
+/// [`MonitorModelState`] and [`PluggableMonitorSettings`].
+///
+/// Only the set settings are present, i.e. the UI settings are `None`
+/// when none of them is set, same for the pluggable monitor settings.
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::{
+///     EndOfLine, LabelType, MonitorModelState, MonitorSettings, PluggableMonitorSetting,
+/// };
+///
+/// let baudrate = PluggableMonitorSetting {
+///     id: Some("baudrate".to_string()),
+///     label: Some("Baudrate".to_string()),
+///     r#type: Some(LabelType::Enum),
+///     values: vec!["9600".into(), "115200".into()],
+///     selected_value: "9600".to_string(),
+/// };
+///
+/// let settings = MonitorSettings::builder()
+///     .dark_theme(true)
+///     .line_ending(EndOfLine::NewLine)
+///     .connected(true)
+///     .pluggable_setting("baudrate", baudrate.clone())
+///     .build();
+///
+/// assert_eq!(
+///     Some(MonitorModelState {
+///         dark_theme: Some(true),
+///         line_ending: Some(EndOfLine::NewLine),
+///         connected: Some(true),
+///         ..Default::default()
+///     }),
+///     settings.monitor_ui_settings
+/// );
+/// assert_eq!(
+///     Some(&baudrate),
+///     settings.pluggable_monitor_settings.unwrap().get("baudrate")
+/// );
+///
+/// assert_eq!(MonitorSettings::default(), MonitorSettings::builder().build());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MonitorSettingsBuilder {
+    settings: MonitorSettings,
+}
+
+impl MonitorSettingsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [`MonitorModelState::autoscroll`].
+    pub fn autoscroll(self, autoscroll: bool) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.autoscroll = Some(autoscroll))
+    }
+
+    /// See [`MonitorModelState::timestamp`].
+    pub fn timestamp(self, timestamp: bool) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.timestamp = Some(timestamp))
+    }
+
+    /// See [`MonitorModelState::line_ending`].
+    pub fn line_ending(self, line_ending: EndOfLine) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.line_ending = Some(line_ending))
+    }
+
+    /// See [`MonitorModelState::interpolate`].
+    pub fn interpolate(self, interpolate: bool) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.interpolate = Some(interpolate))
+    }
+
+    /// See [`MonitorModelState::dark_theme`].
+    pub fn dark_theme(self, dark_theme: bool) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.dark_theme = Some(dark_theme))
+    }
+
+    /// See [`MonitorModelState::ws_port`].
+    ///
+    /// Changing it closes the current connection, see [`Client::set_monitor_settings`].
+    ///
+    /// [`Client::set_monitor_settings`]: crate::Client::set_monitor_settings
+    pub fn ws_port(self, ws_port: u16) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.ws_port = Some(ws_port))
+    }
+
+    /// See [`MonitorModelState::serial_port`].
+    pub fn serial_port(self, serial_port: impl Into<String>) -> Self {
+        let serial_port = serial_port.into();
+
+        self.ui_setting(|ui_settings| ui_settings.serial_port = Some(serial_port))
+    }
+
+    /// See [`MonitorModelState::connected`].
+    pub fn connected(self, connected: bool) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.connected = Some(connected))
+    }
+
+    /// See [`MonitorModelState::generate`].
+    pub fn generate(self, generate: bool) -> Self {
+        self.ui_setting(|ui_settings| ui_settings.generate = Some(generate))
+    }
+
+    /// Adds (or replaces) the pluggable monitor setting with the given `id`.
+    pub fn pluggable_setting(
+        mut self,
+        id: impl Into<String>,
+        setting: PluggableMonitorSetting,
+    ) -> Self {
+        self.settings
+            .pluggable_monitor_settings
+            .get_or_insert_with(Default::default)
+            .insert(id.into(), setting);
+        self
+    }
+
+    pub fn build(self) -> MonitorSettings {
+        self.settings
+    }
+
+    fn ui_setting(mut self, set: impl FnOnce(&mut MonitorModelState)) -> Self {
+        set(self
+            .settings
+            .monitor_ui_settings
+            .get_or_insert_with(Default::default));
+        self
+    }
+}
+
+/// The version of the [`MonitorSettings`] schema, as known by this crate.
+///
+/// This is crate-side metadata only, the Arduino Serial Plotter does not
+/// send nor require it. Use [`MonitorSettings::detect_version`] to guess it
+/// from a received settings payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SchemaVersion {
+    /// The UI state shared with the Arduino Serial Monitor:
+    /// `autoscroll`, `timestamp` and `lineEnding`.
+    V1,
+    /// Adds the Serial Plotter specific UI state:
+    /// `interpolate`, `darkTheme`, `wsPort`, `serialPort`, `connected` and `generate`.
+    V2,
+}
+
+impl SchemaVersion {
+    /// The latest schema version modeled by [`MonitorSettings`].
+    pub const LATEST: Self = Self::V2;
+}
+
+/// The [JSON Schema](https://json-schema.org/draft/2020-12/schema) of the [`MonitorSettings`]
+/// (the `data` of `ON_SETTINGS_DID_CHANGE` and `CHANGE_SETTINGS` commands),
+/// e.g. for validating settings payloads in a front-end.
+///
+/// The schema follows the serialized form, i.e. the `monitorUISettings` field name,
+/// the camelCase [`MonitorModelState`] fields and the raw characters of [`EndOfLine`].
+/// Booleans are also allowed as `"true"`/`"false"` strings as they are accepted by [`MonitorModelState`].
+///
+/// # Examples
+///
+/// ```
+/// use arduino_plotter::protocol::settings_schema;
+///
+/// let schema = settings_schema();
+/// let ui_settings = &schema["$defs"]["MonitorModelState"];
+///
+/// assert!(schema["properties"]["monitorUISettings"].is_object());
+/// assert!(ui_settings["properties"]["darkTheme"].is_object());
+/// assert_eq!(
+///     serde_json::json!(["", "\n", "\r", "\r\n"]),
+///     schema["$defs"]["EndOfLine"]["enum"]
+/// );
+/// ```
+pub fn settings_schema() -> serde_json::Value {
+    let boolean = serde_json::json!({
+        "oneOf": [
+            { "type": "boolean" },
+            { "type": "string", "enum": ["true", "false"] }
+        ]
+    });
+    let described = |description: &str, schema: &serde_json::Value| {
+        let mut schema = schema.clone();
+        schema["description"] = description.into();
+
+        schema
+    };
+
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "MonitorSettings",
+        "type": "object",
+        "properties": {
+            "pluggableMonitorSettings": {
+                "type": "object",
+                "description": "The pluggable monitor settings by their id",
+                "additionalProperties": { "$ref": "#/$defs/PluggableMonitorSetting" }
+            },
+            "monitorUISettings": { "$ref": "#/$defs/MonitorModelState" }
+        },
+        "$defs": {
+            "PluggableMonitorSetting": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": ["string", "null"] },
+                    "label": { "type": ["string", "null"] },
+                    "type": {
+                        "type": ["string", "null"],
+                        "examples": ["enum", "number", "boolean"]
+                    },
+                    "values": { "type": "array", "items": { "$ref": "#/$defs/SettingValue" } },
+                    "selectedValue": { "type": "string" }
+                },
+                "required": ["selectedValue"]
+            },
+            "SettingValue": {
+                "oneOf": [
+                    { "type": "string" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "label": { "type": ["string", "null"] },
+                            "value": { "type": "string" }
+                        },
+                        "required": ["value"]
+                    }
+                ]
+            },
+            "MonitorModelState": {
+                "type": "object",
+                "properties": {
+                    "autoscroll": described("Used by the serial monitors to stick at the bottom of the window", &boolean),
+                    "timestamp": described("Enable timestamp next to the actual data used by the serial monitors", &boolean),
+                    "lineEnding": { "$ref": "#/$defs/EndOfLine" },
+                    "interpolate": described("Enables interpolation of the chart", &boolean),
+                    "darkTheme": described("Whether to enable Dark theme or stick to the Light theme", &boolean),
+                    "wsPort": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": u16::MAX,
+                        "description": "The current websocket port where the communication happens"
+                    },
+                    "serialPort": {
+                        "type": "string",
+                        "description": "The port at which the pluggable monitor is connected to"
+                    },
+                    "connected": described("The connection status of the pluggable monitor to the board", &boolean),
+                    "generate": described("Enable mocked data generation", &boolean)
+                }
+            },
+            "EndOfLine": {
+                "description": "The line ending used when sending a message to the board",
+                "enum": EndOfLine::EOL
+            }
+        }
+    })
+}
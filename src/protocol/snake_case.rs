@@ -0,0 +1,212 @@
+//! `snake_case` JSON representation of the [`MonitorSettings`] for
+//! front-ends other than the Arduino Serial Plotter.
+//!
+//! The Arduino Serial Plotter uses `camelCase` keys which is the default
+//! representation of the [`protocol`](crate::protocol) types.
+//! Only the field names are renamed, the [`PluggableMonitorSettings`] keys
+//! (i.e. the setting ids) are left untouched.
+//!
+//! # Examples
+//!
+//! ```
+//! use arduino_plotter::protocol::{
+//!     snake_case::SnakeCase, EndOfLine, MonitorModelState, MonitorSettings,
+//! };
+//!
+//! let settings = MonitorSettings {
+//!     monitor_ui_settings: Some(MonitorModelState {
+//!         dark_theme: Some(true),
+//!         line_ending: Some(EndOfLine::NewLine),
+//!         ..Default::default()
+//!     }),
+//!     ..Default::default()
+//! };
+//!
+//! let json = serde_json::json!({
+//!     "monitor_ui_settings": {
+//!         "line_ending": "\n",
+//!         "dark_theme": true,
+//!     }
+//! });
+//! assert_eq!(json, serde_json::to_value(SnakeCase(&settings)).unwrap());
+//!
+//! let from_json = serde_json::from_value::<SnakeCase<MonitorSettings>>(json).unwrap();
+//! assert_eq!(Some(true), from_json.0.monitor_ui_settings.unwrap().dark_theme);
+//!
+//! // setting ids are kept as they are
+//! let json = serde_json::json!({
+//!     "pluggable_monitor_settings": {
+//!         "otherSetting": {
+//!             "id": "otherSetting",
+//!             "label": "Other Setting",
+//!             "type": "enum",
+//!             "values": ["A", "B"],
+//!             "selected_value": "B",
+//!         }
+//!     }
+//! });
+//! let from_json = serde_json::from_value::<SnakeCase<MonitorSettings>>(json.clone()).unwrap();
+//! let pluggable_settings = from_json.0.pluggable_monitor_settings.as_ref().unwrap();
+//! assert_eq!("B", pluggable_settings["otherSetting"].selected_value);
+//! assert_eq!(json, serde_json::to_value(&from_json).unwrap());
+//! ```
+//!
+//! It can also be used for fields with `#[serde(with = "arduino_plotter::protocol::snake_case")]`.
+//!
+//! [`PluggableMonitorSettings`]: crate::protocol::PluggableMonitorSettings
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::protocol::MonitorSettings;
+
+/// Wrapper (de)serializing the [`MonitorSettings`] with `snake_case` keys.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakeCase<T>(pub T);
+
+impl Serialize for SnakeCase<&MonitorSettings> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.0, serializer)
+    }
+}
+
+impl Serialize for SnakeCase<MonitorSettings> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SnakeCase<MonitorSettings> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(SnakeCase)
+    }
+}
+
+/// Serializes the [`MonitorSettings`] with `snake_case` keys.
+pub fn serialize<S: Serializer>(
+    monitor_settings: &MonitorSettings,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let camel_case = serde_json::to_value(monitor_settings).map_err(serde::ser::Error::custom)?;
+
+    rename_settings(camel_case, Case::Snake).serialize(serializer)
+}
+
+/// Deserializes the [`MonitorSettings`] from `snake_case` keys.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<MonitorSettings, D::Error> {
+    let snake_case = Value::deserialize(deserializer)?;
+
+    serde_json::from_value(rename_settings(snake_case, Case::Camel)).map_err(D::Error::custom)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Case {
+    Camel,
+    Snake,
+}
+
+impl Case {
+    /// The keys of [`MonitorSettings`] which can't be converted by the general rules.
+    const SETTINGS_KEYS: &'static [(&'static str, &'static str)] = &[
+        ("pluggableMonitorSettings", "pluggable_monitor_settings"),
+        ("monitorUISettings", "monitor_ui_settings"),
+    ];
+
+    fn settings_key(self, key: &str) -> Option<&'static str> {
+        Self::SETTINGS_KEYS
+            .iter()
+            .find_map(|&(camel, snake)| match self {
+                Case::Camel if key == snake => Some(camel),
+                Case::Snake if key == camel => Some(snake),
+                _ => None,
+            })
+    }
+
+    fn convert(self, key: &str) -> String {
+        match self {
+            Case::Camel => {
+                let mut converted = String::with_capacity(key.len());
+                let mut uppercase_next = false;
+
+                for char in key.chars() {
+                    if char == '_' {
+                        uppercase_next = true;
+                    } else if uppercase_next {
+                        converted.extend(char.to_uppercase());
+                        uppercase_next = false;
+                    } else {
+                        converted.push(char);
+                    }
+                }
+
+                converted
+            }
+            Case::Snake => {
+                let mut converted = String::with_capacity(key.len() + 4);
+
+                for char in key.chars() {
+                    if char.is_uppercase() {
+                        if !converted.is_empty() {
+                            converted.push('_');
+                        }
+                        converted.extend(char.to_lowercase());
+                    } else {
+                        converted.push(char);
+                    }
+                }
+
+                converted
+            }
+        }
+    }
+}
+
+fn rename_settings(value: Value, case: Case) -> Value {
+    let Value::Object(settings) = value else {
+        return value;
+    };
+
+    let settings = settings
+        .into_iter()
+        .map(|(key, value)| {
+            let renamed = case
+                .settings_key(&key)
+                .map(ToString::to_string)
+                .unwrap_or_else(|| case.convert(&key));
+
+            let value = match Case::Snake.settings_key(&key).unwrap_or(key.as_str()) {
+                // keep the setting ids and only rename the setting fields
+                "pluggable_monitor_settings" => match value {
+                    Value::Object(pluggable_settings) => Value::Object(
+                        pluggable_settings
+                            .into_iter()
+                            .map(|(id, setting)| (id, rename_keys(setting, case)))
+                            .collect(),
+                    ),
+                    value => value,
+                },
+                _ => rename_keys(value, case),
+            };
+
+            (renamed, value)
+        })
+        .collect::<Map<_, _>>();
+
+    Value::Object(settings)
+}
+
+/// Renames the keys of a JSON object without recursing into nested values.
+fn rename_keys(value: Value, case: Case) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (case.convert(&key), value))
+                .collect(),
+        ),
+        value => value,
+    }
+}
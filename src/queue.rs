@@ -0,0 +1,357 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use thiserror::Error;
+use tokio::sync::Notify;
+
+use crate::log::{debug, error, trace};
+use crate::{
+    protocol::{InconsistentColumns, MonitorSettings},
+    Client, SendError,
+};
+
+/// What happens when a data frame is sent to a full [`QueuedClient`].
+///
+/// [`OverflowPolicy::DropOldest`] favours latency, i.e. the plot stays near real-time,
+/// while [`OverflowPolicy::Block`] and [`OverflowPolicy::Error`] favour completeness,
+/// i.e. no frame is lost without the caller knowing about it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until there's room in the queue.
+    #[default]
+    Block,
+    /// Return [`QueueError::Full`] without queueing the frame.
+    Error,
+    /// Evict the oldest queued frame to make room for the new one.
+    ///
+    /// Evicted frames are counted in [`QueuedClient::dropped_frames`].
+    DropOldest,
+}
+
+/// Errors when queueing a data frame with [`QueuedClient`].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueueError {
+    /// The queue is full and the [`OverflowPolicy::Error`] is used.
+    #[error("The data frames queue is full")]
+    Full,
+    /// Sending a frame over the websocket failed, no more frames will be sent.
+    #[error("The queued client has been closed")]
+    Closed,
+    /// The data frame is inconsistent, it has not been queued,
+    /// see [`Client::set_frame_validation`].
+    #[error(transparent)]
+    InconsistentColumns(#[from] InconsistentColumns),
+}
+
+/// A [`Client`] with a bounded queue of data frames which are sent in the
+/// background by a spawned task.
+///
+/// When the Arduino Serial Plotter can't keep up with the sent data frames the
+/// [`OverflowPolicy`] decides what happens with the new frames.
+///
+/// Settings are not queued, see [`QueuedClient::set_monitor_settings`].
+///
+/// Cheap to clone as it has an internal Atomic reference counter ([`Arc`]) for the queue.
+/// The background task stops after the last clone is dropped and the queue has been emptied.
+///
+/// ```no_run
+/// use arduino_plotter::{Client, OverflowPolicy, QueuedClient};
+///
+/// async fn plot(client: Client) {
+///     let queued = QueuedClient::new(client, 32, OverflowPolicy::DropOldest);
+///
+///     for i in 0..1_000 {
+///         let line = format!("L1:{i}\n");
+///         queued.send(&[&line]).await.expect("Should not be closed");
+///     }
+///
+///     println!("Dropped frames: {}", queued.dropped_frames());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueuedClient {
+    handle: Arc<Handle>,
+}
+
+#[derive(Debug)]
+struct Handle {
+    shared: Arc<Shared>,
+    client: Client,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.frame_queued.notify_one();
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    capacity: usize,
+    policy: OverflowPolicy,
+    queue: Mutex<VecDeque<String>>,
+    /// Notifies the background task for a newly queued frame or for closing.
+    frame_queued: Notify,
+    /// Notifies a blocked sender that there's room in the queue.
+    frame_sent: Notify,
+    /// Notifies the draining callers that all the queued frames have been sent.
+    drained: Notify,
+    /// A frame has been taken from the queue and it's being sent.
+    in_flight: AtomicBool,
+    /// A handle has been dropped or sending a frame has failed.
+    closed: AtomicBool,
+    /// Sending a frame over the websocket failed.
+    failed: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl QueuedClient {
+    /// Creates a new [`QueuedClient`] queueing up to `capacity` data frames
+    /// and spawns the task sending them with the given [`Client`].
+    ///
+    /// # Panics
+    ///
+    /// When `capacity` is `0` or when called outside of a tokio runtime.
+    pub fn new(client: Client, capacity: usize, policy: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "Queue capacity should be greater than 0");
+
+        let shared = Arc::new(Shared {
+            capacity,
+            policy,
+            queue: Mutex::new(VecDeque::with_capacity(capacity)),
+            frame_queued: Notify::new(),
+            frame_sent: Notify::new(),
+            drained: Notify::new(),
+            in_flight: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            failed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+        });
+
+        tokio::spawn(run_queue(shared.clone(), client.clone()));
+
+        Self {
+            handle: Arc::new(Handle { shared, client }),
+        }
+    }
+
+    /// Queue a Data lines message to be sent to the Arduino Serial Plotter UI.
+    ///
+    /// The frame is validated before it's queued, see [`Client::set_frame_validation`].
+    ///
+    /// ```
+    /// use arduino_plotter::{
+    ///     protocol::InconsistentColumns, Client, OverflowPolicy, QueueError, QueuedClient,
+    /// };
+    /// use futures_util::StreamExt;
+    /// use tokio_websockets::{ClientBuilder, ServerBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+    ///     let mut ui = ClientBuilder::new().take_over(ui_io);
+    ///     let (ws_sink, _ws_stream) = ServerBuilder::new().serve(plotter_io).split();
+    ///     let client = Client::new(ws_sink);
+    ///     client.set_frame_validation(true);
+    ///     let queued = QueuedClient::new(client, 8, OverflowPolicy::Error);
+    ///
+    ///     assert_eq!(
+    ///         Err(QueueError::InconsistentColumns(InconsistentColumns {
+    ///             expected: 1,
+    ///             mismatched: vec![(1, 2)],
+    ///         })),
+    ///         queued.send(&["L1:1\n", "L1:1,L2:2\n"]).await
+    ///     );
+    ///
+    ///     queued.send(&["L1:1\n"]).await.unwrap();
+    ///     let data = ui.next().await.unwrap().unwrap();
+    ///     assert_eq!(Some(r#"["L1:1\n"]"#), data.as_text());
+    /// }
+    /// ```
+    pub async fn send(&self, data: &[&str]) -> Result<(), QueueError> {
+        self.push(self.handle.client.data_json(data)?).await
+    }
+
+    /// Queue a Data lines message of owned [`String`]s to be sent to the Arduino Serial Plotter UI.
+    pub async fn send_strings(&self, lines: &[String]) -> Result<(), QueueError> {
+        self.push(self.handle.client.data_json(lines)?).await
+    }
+
+    /// Send a [`MonitorSettings`] to the Arduino Serial Plotter UI bypassing the queue.
+    ///
+    /// See [`Client::set_monitor_settings`].
+    pub async fn set_monitor_settings(
+        &self,
+        monitor_settings: MonitorSettings,
+    ) -> Result<(), SendError> {
+        self.handle
+            .client
+            .set_monitor_settings(monitor_settings)
+            .await
+    }
+
+    /// Waits until all the queued frames have been sent and then drains the [`Client`],
+    /// see [`Client::drain`].
+    ///
+    /// Returns [`QueueError::Closed`] when sending a frame fails.
+    /// Unlike dropping the [`QueuedClient`], it's guaranteed that the frames have been sent
+    /// once `drain` returns, e.g. before the process exits.
+    pub async fn drain(&self) -> Result<(), QueueError> {
+        let shared = &self.handle.shared;
+
+        loop {
+            let drained = shared.drained.notified();
+            tokio::pin!(drained);
+            // register for a notification before checking the queue
+            // in order to not miss the queue being drained
+            drained.as_mut().enable();
+
+            if shared.failed.load(Ordering::Acquire) {
+                return Err(QueueError::Closed);
+            }
+
+            let is_drained = {
+                let queue = shared.queue.lock().unwrap();
+                queue.is_empty() && !shared.in_flight.load(Ordering::Acquire)
+            };
+            if is_drained {
+                break;
+            }
+
+            drained.await;
+        }
+
+        self.handle.client.drain().await.map_err(|err| {
+            debug!(?err, "Draining the client failed");
+            QueueError::Closed
+        })
+    }
+
+    /// Pauses sending the queued frames, the frames sent in the meantime are queued
+    /// according to the [`OverflowPolicy`].
+    ///
+    /// See [`Client::pause`].
+    pub fn pause(&self) {
+        self.handle.client.pause()
+    }
+
+    /// Resumes sending the queued frames.
+    ///
+    /// See [`Client::resume`].
+    pub fn resume(&self) {
+        self.handle.client.resume()
+    }
+
+    /// Whether sending the queued frames is paused.
+    pub fn is_paused(&self) -> bool {
+        self.handle.client.is_paused()
+    }
+
+    /// The number of frames evicted by the [`OverflowPolicy::DropOldest`] policy.
+    pub fn dropped_frames(&self) -> u64 {
+        self.handle.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// The number of frames currently waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.handle.shared.queue.lock().unwrap().len()
+    }
+
+    /// Whether there are no frames waiting in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The [`OverflowPolicy`] used for the queue.
+    pub fn policy(&self) -> OverflowPolicy {
+        self.handle.shared.policy
+    }
+
+    async fn push(&self, frame: String) -> Result<(), QueueError> {
+        let shared = &self.handle.shared;
+
+        loop {
+            let frame_sent = shared.frame_sent.notified();
+            tokio::pin!(frame_sent);
+            // register for a notification before checking the queue
+            // in order to not miss a sent frame
+            frame_sent.as_mut().enable();
+
+            if shared.failed.load(Ordering::Acquire) {
+                return Err(QueueError::Closed);
+            }
+
+            {
+                let mut queue = shared.queue.lock().unwrap();
+
+                if queue.len() < shared.capacity {
+                    queue.push_back(frame);
+                    shared.frame_queued.notify_one();
+
+                    return Ok(());
+                }
+
+                match shared.policy {
+                    OverflowPolicy::Block => {}
+                    OverflowPolicy::Error => return Err(QueueError::Full),
+                    OverflowPolicy::DropOldest => {
+                        queue.pop_front();
+                        queue.push_back(frame);
+                        shared.dropped.fetch_add(1, Ordering::Relaxed);
+                        trace!("Queue is full, oldest frame dropped");
+
+                        return Ok(());
+                    }
+                }
+            }
+
+            frame_sent.await;
+        }
+    }
+}
+
+async fn run_queue(shared: Arc<Shared>, client: Client) {
+    loop {
+        let frame = {
+            let mut queue = shared.queue.lock().unwrap();
+            let frame = queue.pop_front();
+            shared.in_flight.store(frame.is_some(), Ordering::Release);
+
+            frame
+        };
+
+        match frame {
+            Some(frame) => {
+                shared.frame_sent.notify_one();
+
+                if let Err(err) = client.send_text(frame).await {
+                    error!(?err, "Sending queued data frame failed, closing queue");
+                    shared.failed.store(true, Ordering::Release);
+                    shared.closed.store(true, Ordering::Release);
+                    shared.queue.lock().unwrap().clear();
+                    // wake up all blocked senders and draining callers
+                    shared.frame_sent.notify_waiters();
+                    shared.drained.notify_waiters();
+
+                    return;
+                }
+            }
+            None if shared.closed.load(Ordering::Acquire) => {
+                shared.drained.notify_waiters();
+                debug!("Queued client closed");
+
+                return;
+            }
+            None => {
+                shared.drained.notify_waiters();
+                shared.frame_queued.notified().await
+            }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use core::{future::Future, time::Duration};
+use std::{collections::VecDeque, sync::Arc};
+
+use futures_util::{future::BoxFuture, FutureExt};
+use http::{HeaderMap, Uri};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use crate::log::{debug, error, trace};
+use crate::{
+    api::serialize,
+    protocol::{InconsistentColumns, MiddlewareCommand, MonitorSettings},
+    Client, ConnectError, SendError, Server,
+};
+
+/// Errors when sending with [`ReconnectingClient`].
+#[derive(Debug, Error)]
+pub enum ReconnectError {
+    /// Sending failed and all the reconnect attempts have failed.
+    #[error("Failed to reconnect: {0}")]
+    Connect(#[from] ConnectError),
+    /// Sending failed right after a successful reconnect.
+    #[error(transparent)]
+    Send(#[from] SendError),
+    /// The data frame is inconsistent, nothing has been sent,
+    /// see [`Client::set_frame_validation`].
+    #[error(transparent)]
+    InconsistentColumns(#[from] InconsistentColumns),
+}
+
+/// How the delay before a reconnect attempt is randomized within the backoff window
+/// of a [`ReconnectPolicy`].
+///
+/// Randomizing the delays prevents many clients dropped at the same time,
+/// e.g. by a server restart, from reconnecting in synchronized storms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Jitter {
+    /// The delay is the whole backoff window.
+    None,
+    /// The delay is random between `0` and the backoff window.
+    Full,
+    /// The delay is half of the backoff window plus a random delay between `0`
+    /// and the other half, i.e. there's always some backoff.
+    #[default]
+    Equal,
+}
+
+/// When and how many times [`ReconnectingClient`] attempts to reconnect.
+///
+/// The backoff window of the `n`-th attempt (starting from 1) doubles with every attempt,
+/// `min(max_delay, base_delay * 2^(n - 1))`, and the actual delay before the attempt is
+/// randomized within it according to the [`Jitter`].
+///
+/// By default, reconnecting is attempted 3 times with a 1 second base delay,
+/// up to 30 seconds and with [`Jitter::Equal`].
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use arduino_plotter::{Jitter, ReconnectPolicy};
+///
+/// let policy = ReconnectPolicy::default()
+///     .base_delay(Duration::from_millis(100))
+///     .max_delay(Duration::from_millis(300))
+///     .jitter(Jitter::None);
+///
+/// assert_eq!(Duration::from_millis(100), policy.delay(1));
+/// assert_eq!(Duration::from_millis(200), policy.delay(2));
+/// assert_eq!(Duration::from_millis(300), policy.delay(3));
+///
+/// let equal = policy.jitter(Jitter::Equal);
+/// let delay = equal.delay(2);
+/// assert!(Duration::from_millis(100) <= delay && delay <= Duration::from_millis(200));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    attempts: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: Jitter,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            jitter: Jitter::default(),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Sets how many times reconnecting is attempted after sending fails, at least once.
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Sets the backoff window of the first attempt.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Sets the maximum backoff window.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Sets the [`Jitter`] of the delays.
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The randomized delay before the `attempt`-th attempt, starting from 1.
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let exponent = u32::try_from(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        let window = 2_u32
+            .checked_pow(exponent)
+            .and_then(|factor| self.base_delay.checked_mul(factor))
+            .map_or(self.max_delay, |window| window.min(self.max_delay));
+
+        match self.jitter {
+            Jitter::None => window,
+            Jitter::Full => window.mul_f64(rand::random::<f64>()),
+            Jitter::Equal => window / 2 + (window / 2).mul_f64(rand::random::<f64>()),
+        }
+    }
+}
+
+/// A [`Client`] connected with [`Client::connect_with_headers`], or a custom connector
+/// (see [`ReconnectingClient::with_connector`]), which reconnects when sending fails.
+///
+/// The settings sent with [`ReconnectingClient::set_monitor_settings`] are cached
+/// (merged with [`MonitorSettings::merge`]) and with
+/// [`ReconnectingClient::replay_on_reconnect`] the last sent data frames are kept as well.
+///
+/// # Ordering
+///
+/// After a successful reconnect the following is sent before the live data is resumed:
+/// 1. the cached settings
+/// 2. the replayed data frames, oldest first
+/// 3. the data frame whose sending has failed
+///
+/// The old connection might have partially received the replayed frames before
+/// dropping, so a UI which survives the reconnect (e.g. a proxy reconnect) can show
+/// duplicate points.
+///
+/// ```no_run
+/// use arduino_plotter::{ReconnectError, ReconnectingClient};
+/// use http::{HeaderMap, Uri};
+///
+/// async fn plot() -> Result<(), ReconnectError> {
+///     let uri = Uri::from_static("ws://gateway.local:3030");
+///     let client = ReconnectingClient::connect(uri, HeaderMap::new())
+///         .await?
+///         .replay_on_reconnect(100);
+///
+///     for i in 0..1_000 {
+///         client.send(&[&format!("L1:{i}\n")]).await?;
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ReconnectingClient {
+    connector: Arc<Connector>,
+    replay: usize,
+    policy: ReconnectPolicy,
+    state: Mutex<State>,
+}
+
+type Connector =
+    dyn Fn() -> BoxFuture<'static, Result<(Client, Server), ConnectError>> + Send + Sync;
+
+#[derive(Debug)]
+struct State {
+    client: Client,
+    server: Server,
+    settings: Option<MonitorSettings>,
+    history: VecDeque<String>,
+}
+
+impl ReconnectingClient {
+    /// Connects to the `uri` with the given `headers`, see [`Client::connect_with_headers`].
+    ///
+    /// By default, no data frames are replayed and reconnecting follows
+    /// the default [`ReconnectPolicy`].
+    pub async fn connect(uri: Uri, headers: HeaderMap) -> Result<Self, ConnectError> {
+        Self::with_connector(move || Client::connect_with_headers(uri.clone(), headers.clone()))
+            .await
+    }
+
+    /// Connects with the `connect` closure, which is called again on every reconnect attempt,
+    /// e.g. for connecting with fresh credentials or over a custom transport.
+    ///
+    /// ```no_run
+    /// use arduino_plotter::{Client, ConnectError, ReconnectingClient};
+    /// use http::{header, HeaderMap, HeaderValue, Uri};
+    ///
+    /// fn fresh_token() -> HeaderValue {
+    ///     HeaderValue::from_static("Bearer token")
+    /// }
+    ///
+    /// async fn connect() -> Result<ReconnectingClient, ConnectError> {
+    ///     ReconnectingClient::with_connector(|| {
+    ///         let mut headers = HeaderMap::new();
+    ///         headers.insert(header::AUTHORIZATION, fresh_token());
+    ///
+    ///         Client::connect_with_headers(Uri::from_static("ws://gateway.local:3030"), headers)
+    ///     })
+    ///     .await
+    /// }
+    /// ```
+    pub async fn with_connector<F, Fut>(connect: F) -> Result<Self, ConnectError>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(Client, Server), ConnectError>> + Send + 'static,
+    {
+        let connector: Arc<Connector> = Arc::new(move || connect().boxed());
+        let (client, server) = connector().await?;
+
+        Ok(Self {
+            connector,
+            replay: 0,
+            policy: ReconnectPolicy::default(),
+            state: Mutex::new(State {
+                client,
+                server,
+                settings: None,
+                history: VecDeque::new(),
+            }),
+        })
+    }
+
+    /// Sets how many of the last sent data frames are re-sent after a reconnect.
+    pub fn replay_on_reconnect(mut self, count: usize) -> Self {
+        self.replay = count;
+        self
+    }
+
+    /// Sets how many times reconnecting is attempted after sending fails, at least once.
+    ///
+    /// See [`ReconnectPolicy::attempts`].
+    pub fn reconnect_attempts(mut self, attempts: usize) -> Self {
+        self.policy = self.policy.attempts(attempts);
+        self
+    }
+
+    /// Sets the backoff window of the first reconnect attempt.
+    ///
+    /// See [`ReconnectPolicy::base_delay`].
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.policy = self.policy.base_delay(delay);
+        self
+    }
+
+    /// Sets the whole [`ReconnectPolicy`].
+    pub fn reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The [`Client`] of the current connection.
+    pub async fn client(&self) -> Client {
+        self.state.lock().await.client.clone()
+    }
+
+    /// The [`Server`] of the current connection, a new one is created on every reconnect.
+    pub async fn server(&self) -> Server {
+        self.state.lock().await.server.clone()
+    }
+
+    /// Send a Data lines message, reconnecting when sending fails.
+    pub async fn send(&self, data: &[&str]) -> Result<(), ReconnectError> {
+        let json = self.state.lock().await.client.data_json(data)?;
+
+        self.send_frame(json).await
+    }
+
+    /// Send a Data lines message of owned [`String`]s, reconnecting when sending fails.
+    pub async fn send_strings(&self, lines: &[String]) -> Result<(), ReconnectError> {
+        let json = self.state.lock().await.client.data_json(lines)?;
+
+        self.send_frame(json).await
+    }
+
+    /// Send a [`MonitorSettings`] and cache it for the reconnects,
+    /// reconnecting when sending fails.
+    pub async fn set_monitor_settings(
+        &self,
+        monitor_settings: MonitorSettings,
+    ) -> Result<(), ReconnectError> {
+        let mut state = self.state.lock().await;
+        // a refused port change is neither cached nor a reason to reconnect
+        state.client.check_ws_port(&monitor_settings)?;
+
+        let json = serialize(&MiddlewareCommand(monitor_settings.clone()));
+        state.settings = Some(match state.settings.take() {
+            Some(cached) => cached.merge(monitor_settings.clone()),
+            None => monitor_settings.clone(),
+        });
+
+        if let Err(err) = state
+            .client
+            .send_settings_text(&monitor_settings, json)
+            .await
+        {
+            debug!(?err, "Sending settings failed, reconnecting");
+            // the cached settings, including the new ones, are sent on reconnect
+            self.reconnect(&mut state).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_frame(&self, json: String) -> Result<(), ReconnectError> {
+        let mut state = self.state.lock().await;
+
+        if let Err(err) = state.client.send_text(json.clone()).await {
+            debug!(?err, "Sending data frame failed, reconnecting");
+            self.reconnect(&mut state).await?;
+            state.client.send_text(json.clone()).await?;
+        }
+
+        if self.replay > 0 {
+            if state.history.len() == self.replay {
+                state.history.pop_front();
+            }
+            state.history.push_back(json);
+        }
+
+        Ok(())
+    }
+
+    /// Reconnects and re-sends the cached settings and the replayed data frames.
+    async fn reconnect(&self, state: &mut State) -> Result<(), ReconnectError> {
+        let mut attempt = 0;
+        let (client, server) = loop {
+            attempt += 1;
+            tokio::time::sleep(self.policy.delay(attempt)).await;
+
+            match (self.connector)().await {
+                Ok(connection) => break connection,
+                Err(err) if attempt >= self.policy.attempts => {
+                    error!(?err, attempt, "Reconnecting failed");
+                    return Err(err.into());
+                }
+                Err(err) => debug!(?err, attempt, "Reconnect attempt failed"),
+            }
+        };
+        debug!(attempt, "Reconnected");
+
+        state.client = client;
+        state.server = server;
+
+        if let Some(settings) = &state.settings {
+            let json = serialize(&MiddlewareCommand(settings.clone()));
+            state.client.send_settings_text(settings, json).await?;
+        }
+
+        trace!(frames = state.history.len(), "Replaying data frames");
+        for frame in &state.history {
+            state.client.send_text(frame.clone()).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for ReconnectingClient {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ReconnectingClient")
+            .field("replay", &self.replay)
+            .field("policy", &self.policy)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,320 @@
+use core::time::Duration;
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures_util::StreamExt;
+use tokio::{
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinSet,
+};
+use tokio_websockets::ServerBuilder;
+
+use crate::log::{debug, error, info};
+use crate::{Client, Server};
+
+#[doc(no_inline)]
+pub use tokio_util::sync::CancellationToken;
+
+/// How long an accepted connection has to complete the websocket handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accepts Arduino Serial Plotter connections on the `listener` until
+/// `shutdown` is cancelled.
+///
+/// For every connection the websocket handshake is performed and the `handler`
+/// is spawned with the connection's [`Client`], [`Server`] and [`ConnectionGuard`].
+/// Failed handshakes and the ones which don't complete within 10 seconds are logged and skipped.
+/// The [`Client`] knows the port the UI has connected to, so the settings can carry it
+/// as the `wsPort`, see [`Client::set_ws_port`].
+///
+/// On shutdown, every connection which is still active:
+/// 1. receives a settings message with `connected: false`
+/// 2. is closed with [`Client::close`]
+/// 3. has its [`ConnectionGuard::cancelled`] future completed
+///
+/// and `serve` returns once all the spawned handlers have finished.
+///
+/// The handler should return once the [`Server`] stream ends so that the
+/// finished connection gets cleaned up.
+///
+/// See the `shutdown` example for a complete usage.
+pub async fn serve<H, Fut>(listener: TcpListener, shutdown: CancellationToken, handler: H)
+where
+    H: Fn(Client, Server, ConnectionGuard) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    serve_connections(listener, shutdown, None, handler).await
+}
+
+/// Binds the `addr` and serves every Arduino Serial Plotter connection with the `handler`,
+/// spawned with the connection's [`Client`] and [`Server`], until the process exits.
+///
+/// Same as [`serve`] without a shutdown, so it only returns when binding fails.
+///
+/// ```no_run
+/// use arduino_plotter::serve_addr;
+/// use futures_util::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() -> std::io::Result<()> {
+///     serve_addr("127.0.0.1:3030", |client, mut server| async move {
+///         let _ = client.send(&["L1:1,L2:2\n"]).await;
+///
+///         while let Some(command) = server.next().await {
+///             println!("Received: {command:?}");
+///         }
+///     })
+///     .await
+/// }
+/// ```
+pub async fn serve_addr<A, H, Fut>(addr: A, handler: H) -> std::io::Result<()>
+where
+    A: ToSocketAddrs,
+    H: Fn(Client, Server) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind(addr).await?;
+    info!(addr = ?listener.local_addr(), "Serving the Arduino Serial Plotter connections");
+
+    // never cancelled
+    let shutdown = CancellationToken::new();
+    serve(listener, shutdown, move |client, server, _guard| {
+        handler(client, server)
+    })
+    .await;
+
+    Ok(())
+}
+
+/// Same as [`serve`] but with at most `max_connections` concurrent connections.
+///
+/// When the limit is reached no new connections are accepted until one of the
+/// active connections' handler finishes or a pending handshake fails or times out,
+/// so connections which never send the upgrade request can't hold the slots forever.
+/// This prevents piling up connections when the Arduino Serial Plotter UI
+/// is reloaded repeatedly, i.e. a reconnect storm.
+///
+/// # Panics
+///
+/// When `max_connections` is `0`.
+pub async fn serve_with_limit<H, Fut>(
+    listener: TcpListener,
+    shutdown: CancellationToken,
+    max_connections: usize,
+    handler: H,
+) where
+    H: Fn(Client, Server, ConnectionGuard) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    assert!(
+        max_connections > 0,
+        "At least 1 connection should be allowed"
+    );
+
+    let limit = Arc::new(Semaphore::new(max_connections));
+    serve_connections(listener, shutdown, Some(limit), handler).await
+}
+
+async fn serve_connections<H, Fut>(
+    listener: TcpListener,
+    shutdown: CancellationToken,
+    limit: Option<Arc<Semaphore>>,
+    handler: H,
+) where
+    H: Fn(Client, Server, ConnectionGuard) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let connections = Connections::default();
+    // cancelled only after all connections have been notified and closed
+    let connections_shutdown = CancellationToken::new();
+    let mut tasks = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            (accepted, permit) = accept(&listener, limit.as_ref()) => {
+                let (stream, peer_addr) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        error!(?err, "Failed to accept a connection");
+                        continue;
+                    }
+                };
+
+                let handler = handler.clone();
+                let connections = connections.clone();
+                let cancellation = connections_shutdown.child_token();
+
+                tasks.spawn(async move {
+                    // keep the connection slot until the handler finishes
+                    let _permit = permit;
+
+                    // the port the UI has connected to, i.e. its `wsPort`
+                    let ws_port = stream.local_addr().ok().map(|local_addr| local_addr.port());
+                    let builder = ServerBuilder::new();
+                    let handshake = tokio::time::timeout(HANDSHAKE_TIMEOUT, builder.accept(stream));
+                    let ws_stream = match handshake.await {
+                        Ok(Ok(ws_stream)) => ws_stream,
+                        Ok(Err(err)) => {
+                            error!(%peer_addr, "Error performing HTTP upgrade handshake request: {err}");
+                            return;
+                        }
+                        Err(_elapsed) => {
+                            debug!(%peer_addr, "HTTP upgrade handshake timed out");
+                            return;
+                        }
+                    };
+                    debug!(%peer_addr, "Connection established");
+
+                    let (ws_sink, ws_stream) = ws_stream.split();
+                    let (client, server) = (Client::new(ws_sink), Server::new(ws_stream));
+                    if let Some(ws_port) = ws_port {
+                        client.set_ws_port(ws_port);
+                    }
+                    let guard = connections.register(client.clone(), peer_addr, cancellation);
+                    // the connection stays active until the handler finishes,
+                    // even if it doesn't hold onto the guard
+                    let _registration = guard.registration.clone();
+
+                    handler(client, server, guard).await;
+                });
+            }
+            // clean up the finished connections
+            Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+        }
+    }
+
+    let clients = connections.clients();
+    info!(
+        connections = clients.len(),
+        "Shutting down, closing connections"
+    );
+
+    for client in clients {
+        if let Err(err) = client.set_connected(false).await {
+            debug!(?err, "Failed to notify for the disconnect");
+        }
+        if let Err(err) = client.close().await {
+            debug!(?err, "Failed to close connection");
+        }
+    }
+
+    connections_shutdown.cancel();
+
+    while tasks.join_next().await.is_some() {}
+}
+
+/// Accepts a new connection once there's a free connection slot.
+async fn accept(
+    listener: &TcpListener,
+    limit: Option<&Arc<Semaphore>>,
+) -> (
+    std::io::Result<(TcpStream, SocketAddr)>,
+    Option<OwnedSemaphorePermit>,
+) {
+    let permit = match limit {
+        Some(limit) => {
+            if limit.available_permits() == 0 {
+                debug!("Connections limit reached, waiting for a connection to finish");
+            }
+
+            Some(
+                limit
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("Semaphore is never closed"),
+            )
+        }
+        None => None,
+    };
+
+    (listener.accept().await, permit)
+}
+
+#[derive(Debug, Clone, Default)]
+struct Connections {
+    next_id: Arc<AtomicU64>,
+    clients: Arc<Mutex<HashMap<u64, Client>>>,
+}
+
+impl Connections {
+    fn register(
+        &self,
+        client: Client,
+        peer_addr: SocketAddr,
+        cancellation: CancellationToken,
+    ) -> ConnectionGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.clients.lock().unwrap().insert(id, client);
+
+        ConnectionGuard {
+            registration: Arc::new(Registration {
+                id,
+                peer_addr,
+                connections: self.clone(),
+            }),
+            cancellation,
+        }
+    }
+
+    fn clients(&self) -> Vec<Client> {
+        self.clients.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// Tracks an active connection accepted by [`serve`].
+///
+/// The connection is considered active until its handler has finished and
+/// the guard has been dropped, connections which are no longer active are not
+/// notified on shutdown.
+#[derive(Debug)]
+pub struct ConnectionGuard {
+    registration: Arc<Registration>,
+    cancellation: CancellationToken,
+}
+
+/// Removes the connection from the active ones when dropped.
+#[derive(Debug)]
+struct Registration {
+    id: u64,
+    peer_addr: SocketAddr,
+    connections: Connections,
+}
+
+impl ConnectionGuard {
+    /// A unique identifier of the connection.
+    pub fn id(&self) -> u64 {
+        self.registration.id
+    }
+
+    /// The address of the Arduino Serial Plotter UI.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.registration.peer_addr
+    }
+
+    /// Completes when the shutdown of [`serve`] has been requested.
+    pub async fn cancelled(&self) {
+        self.cancellation.cancelled().await
+    }
+
+    /// Whether the shutdown of [`serve`] has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.connections.clients.lock().unwrap().remove(&self.id);
+        debug!(id = self.id, peer_addr = %self.peer_addr, "Connection finished");
+    }
+}
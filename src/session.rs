@@ -0,0 +1,95 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::log::debug;
+use crate::Client;
+
+/// A registry of the active sessions by the serial port they are plotting,
+/// i.e. [`MonitorModelState::serial_port`], e.g. for routing the data of a device
+/// to the correct Arduino Serial Plotter UI in a lab dashboard.
+///
+/// Cheap to clone as it has an internal Atomic reference counter ([`Arc`]) for the sessions.
+///
+/// ```no_run
+/// use arduino_plotter::{protocol::ClientCommand, Client, Server, SessionRegistry};
+/// use futures_util::StreamExt;
+///
+/// async fn track(registry: SessionRegistry, client: Client, mut server: Server) {
+///     while let Some(Ok(command)) = server.next().await {
+///         if let ClientCommand::ChangeSettings(settings) = command {
+///             let serial_port = settings
+///                 .monitor_ui_settings
+///                 .and_then(|ui_settings| ui_settings.serial_port);
+///
+///             if let Some(serial_port) = serial_port {
+///                 registry.register(serial_port, client.clone());
+///             }
+///         }
+///     }
+/// }
+///
+/// async fn route(registry: &SessionRegistry, serial_port: &str, line: &str) {
+///     if let Some(client) = registry.get(serial_port) {
+///         let _ = client.send(&[line]).await;
+///     }
+/// }
+/// ```
+///
+/// [`MonitorModelState::serial_port`]: crate::protocol::MonitorModelState::serial_port
+#[derive(Debug, Clone, Default)]
+pub struct SessionRegistry {
+    sessions: Arc<Mutex<HashMap<String, Client>>>,
+}
+
+impl SessionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the [`Client`] plotting the `serial_port`, returning the previously
+    /// registered one.
+    pub fn register(&self, serial_port: impl Into<String>, client: Client) -> Option<Client> {
+        let serial_port = serial_port.into();
+        debug!(serial_port, "Session registered");
+
+        self.sessions.lock().unwrap().insert(serial_port, client)
+    }
+
+    /// The [`Client`] plotting the `serial_port`.
+    pub fn get(&self, serial_port: &str) -> Option<Client> {
+        self.sessions.lock().unwrap().get(serial_port).cloned()
+    }
+
+    /// Removes the session of the `serial_port`.
+    pub fn remove(&self, serial_port: &str) -> Option<Client> {
+        self.sessions.lock().unwrap().remove(serial_port)
+    }
+
+    /// Removes all the sessions whose [`Client`] has been closed, see [`Client::is_closed`].
+    ///
+    /// Returns the number of removed sessions.
+    pub fn remove_closed(&self) -> usize {
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_serial_port, client| !client.is_closed());
+
+        before - sessions.len()
+    }
+
+    /// The serial ports of all the registered sessions.
+    pub fn serial_ports(&self) -> Vec<String> {
+        self.sessions.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// The number of registered sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    /// Whether there are no registered sessions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_util::{future::BoxFuture, FutureExt, Sink};
+
+use crate::{protocol::Data, Client, SendError};
+
+/// A [`Client`] adapter which is a [`Sink`] of [`Data`] frames, e.g. for forwarding
+/// a stream of frames with [`StreamExt::forward`](futures_util::StreamExt::forward).
+///
+/// Every frame is sent as a separate Data lines message with [`Client::send`],
+/// closing the sink closes the connection with [`Client::close`].
+///
+/// ```
+/// use arduino_plotter::{protocol::Data, Client};
+/// use futures_util::{stream, StreamExt};
+/// use tokio_websockets::{ClientBuilder, Message, ServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (ui_io, plotter_io) = tokio::io::duplex(1024);
+///     let mut ui = ServerBuilder::new().serve(ui_io);
+///     let (client, _server) = Client::from_transport(ClientBuilder::new().take_over(plotter_io));
+///
+///     let frames = [
+///         Data(vec!["L1:1\n".to_string()]),
+///         Data(vec!["L1:2\n".to_string(), "L1:3\n".to_string()]),
+///     ];
+///     stream::iter(frames)
+///         .map(Ok)
+///         .forward(client.into_sink())
+///         .await
+///         .unwrap();
+///
+///     let message = ui.next().await.unwrap().unwrap();
+///     assert_eq!(Some(r#"["L1:1\n"]"#), message.as_text());
+///     let message = ui.next().await.unwrap().unwrap();
+///     assert_eq!(Some(r#"["L1:2\n","L1:3\n"]"#), message.as_text());
+///     // the sink has closed the connection
+///     assert!(ui.next().await.unwrap().unwrap().is_close());
+/// }
+/// ```
+pub struct ClientSink {
+    client: Client,
+    /// The frame or the Close frame being sent.
+    in_flight: Option<BoxFuture<'static, Result<(), SendError>>>,
+    closing: bool,
+}
+
+impl ClientSink {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            in_flight: None,
+            closing: false,
+        }
+    }
+
+    /// Returns the underlying [`Client`], a frame which is still being sent is cancelled.
+    pub fn into_inner(self) -> Client {
+        self.client
+    }
+
+    fn poll_in_flight(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+        let result = match self.in_flight.as_mut() {
+            Some(in_flight) => ready!(in_flight.poll_unpin(cx)),
+            None => Ok(()),
+        };
+        self.in_flight = None;
+
+        Poll::Ready(result)
+    }
+}
+
+impl Sink<Data<String>> for ClientSink {
+    type Error = SendError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_in_flight(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, frame: Data<String>) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let client = this.client.clone();
+
+        this.in_flight = Some(async move { client.send(&frame).await }.boxed());
+
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // a frame is written to the connection once it's sent
+        self.get_mut().poll_in_flight(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_in_flight(cx))?;
+
+        if this.closing {
+            return Poll::Ready(Ok(()));
+        }
+
+        this.closing = true;
+        let client = this.client.clone();
+        this.in_flight = Some(async move { client.close().await }.boxed());
+
+        this.poll_in_flight(cx)
+    }
+}
+
+impl core::fmt::Debug for ClientSink {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ClientSink")
+            .field("client", &self.client)
+            .field("in_flight", &self.in_flight.is_some())
+            .field("closing", &self.closing)
+            .finish()
+    }
+}
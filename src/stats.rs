@@ -0,0 +1,134 @@
+use core::time::Duration;
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use crate::log::trace;
+use crate::protocol::{parse_variables, Data, ParseOptions};
+
+/// The statistics of a single label (series) over a session, see [`SessionStats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelStats {
+    /// The number of values of the label.
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl LabelStats {
+    fn new(value: f64) -> Self {
+        Self {
+            count: 1,
+            min: value,
+            max: value,
+            mean: value,
+        }
+    }
+
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        // a running mean doesn't overflow on long sessions
+        self.mean += (value - self.mean) / self.count as f64;
+    }
+
+    /// The number of values per second over a session of the given `duration`,
+    /// e.g. the time between the first and the last captured frame.
+    ///
+    /// It's `0` for a zero `duration`.
+    pub fn sampling_rate(&self, duration: Duration) -> f64 {
+        if duration.is_zero() {
+            return 0.0;
+        }
+
+        self.count as f64 / duration.as_secs_f64()
+    }
+}
+
+/// The [`LabelStats`] of every label of a recorded session by its name.
+///
+/// Unlabeled values are named `value N` same as in the Arduino Serial Plotter UI,
+/// see [`DataLine::names`](crate::protocol::DataLine::names).
+///
+/// The values are parsed with [`parse_variables`] with [`ParseOptions::units`],
+/// i.e. trailing units are ignored, and the lines which can't be parsed are skipped.
+/// A label missing in some lines only has the values of the lines it's present in.
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use arduino_plotter::{protocol::Data, SessionStats};
+///
+/// let session = [
+///     Data(vec!["temp:20,humidity:40\n".to_string()]),
+///     Data(vec!["temp:22\n".to_string(), "temp:24,humidity:50\n".to_string()]),
+///     Data(vec!["not a number\n".to_string(), "7,temp:26C\n".to_string()]),
+/// ];
+///
+/// let stats = SessionStats::from_frames(&session);
+/// assert_eq!(3, stats.len());
+///
+/// let temp = stats["temp"];
+/// assert_eq!(4, temp.count);
+/// assert_eq!((20.0, 26.0, 23.0), (temp.min, temp.max, temp.mean));
+/// assert_eq!(2.0, temp.sampling_rate(Duration::from_secs(2)));
+///
+/// // the humidity missing in some lines isn't counted as a zero
+/// let humidity = stats["humidity"];
+/// assert_eq!(2, humidity.count);
+/// assert_eq!((40.0, 50.0, 45.0), (humidity.min, humidity.max, humidity.mean));
+///
+/// assert_eq!(1, stats["value 1"].count);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionStats(pub HashMap<String, LabelStats>);
+
+impl SessionStats {
+    /// Aggregates the values of all the lines of the captured `frames` by label.
+    pub fn from_frames(frames: &[Data<String>]) -> Self {
+        let mut stats = HashMap::<String, LabelStats>::new();
+        let options = ParseOptions { units: true };
+
+        for line in frames.iter().flat_map(|frame| &frame.0) {
+            let variables = match parse_variables(line, options) {
+                Ok(variables) => variables,
+                Err(err) => {
+                    trace!(?err, line, "Line skipped from the session stats");
+                    continue;
+                }
+            };
+
+            for (index, variable) in variables.into_iter().enumerate() {
+                let name = variable
+                    .label
+                    .unwrap_or_else(|| format!("value {}", index + 1));
+
+                match stats.get_mut(&name) {
+                    Some(label_stats) => label_stats.push(variable.value),
+                    None => {
+                        stats.insert(name, LabelStats::new(variable.value));
+                    }
+                }
+            }
+        }
+
+        Self(stats)
+    }
+}
+
+impl Deref for SessionStats {
+    type Target = HashMap<String, LabelStats>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SessionStats {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,58 @@
+use core::fmt::Debug;
+
+use futures_util::{
+    stream::{SplitSink, SplitStream},
+    Sink, Stream, StreamExt,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_websockets::{Error, Message, WebSocketStream};
+
+/// The websocket layer of a connection to the Arduino Serial Plotter UI,
+/// see [`Client::from_transport`].
+///
+/// The [`Client`] only needs to send [`Message`]s (text data and settings messages and
+/// a Close frame) and the [`Server`] only needs to receive them,
+/// so a transport is split into a [`Sink`] and a [`Stream`] of messages.
+///
+/// It's implemented for any `tokio-websockets` [`WebSocketStream`] and another websocket
+/// library can be plugged in by adapting its halves to the `tokio-websockets` [`Message`]s
+/// and [`Error`]s, e.g. with [`SinkExt::with`](futures_util::SinkExt::with) and
+/// [`StreamExt::map`].
+///
+/// [`Client`]: crate::Client
+/// [`Client::from_transport`]: crate::Client::from_transport
+/// [`Server`]: crate::Server
+pub trait WsTransport {
+    /// The sending half used by the [`Client`](crate::Client).
+    type Sink: Sink<Message, Error = Error> + Debug + Send + Unpin + 'static;
+    /// The receiving half used by the [`Server`](crate::Server).
+    type Stream: Stream<Item = Result<Message, Error>> + Debug + Send + Unpin + 'static;
+
+    /// Splits the transport into its sending and receiving halves.
+    fn into_split(self) -> (Self::Sink, Self::Stream);
+}
+
+impl<S> WsTransport for WebSocketStream<S>
+where
+    S: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static,
+{
+    type Sink = SplitSink<WebSocketStream<S>, Message>;
+    type Stream = SplitStream<WebSocketStream<S>>;
+
+    fn into_split(self) -> (Self::Sink, Self::Stream) {
+        self.split()
+    }
+}
+
+/// The type-erased sending half of a [`WsTransport`].
+pub(crate) trait TransportSink: Sink<Message, Error = Error> + Debug + Send + Unpin {}
+
+impl<T> TransportSink for T where T: Sink<Message, Error = Error> + Debug + Send + Unpin {}
+
+/// The type-erased receiving half of a [`WsTransport`].
+pub(crate) trait TransportStream:
+    Stream<Item = Result<Message, Error>> + Debug + Send + Unpin
+{
+}
+
+impl<T> TransportStream for T where T: Stream<Item = Result<Message, Error>> + Debug + Send + Unpin {}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use crate::{protocol::unknown_command, ServerError};
+
+/// A protocol violation of the Arduino Serial Plotter UI reported to the
+/// [`Server::on_violation`] callback.
+///
+/// [`Server::on_violation`]: crate::Server::on_violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// A non-text (e.g. binary) websocket message, see [`ServerError::NonTextMessage`].
+    NonTextMessage,
+    /// A text message which is not a valid command JSON.
+    InvalidJson,
+    /// A command object with a `command` name which is not a known
+    /// [`CommandName`](crate::protocol::CommandName), e.g. sent by a newer webapp version.
+    UnknownCommand,
+    /// A Data lines message (a JSON array of lines), see [`ServerError::IsDataFrame`].
+    DataFrame,
+}
+
+impl ViolationKind {
+    /// The violation of a receive error, `None` for the websocket and the buffer errors.
+    pub(crate) fn of(err: &ServerError, payload: &str) -> Option<Self> {
+        match err {
+            ServerError::Ws(_) | ServerError::BufferFull { .. } => None,
+            ServerError::NonTextMessage => Some(Self::NonTextMessage),
+            ServerError::IsDataFrame => Some(Self::DataFrame),
+            ServerError::Json(_) | ServerError::Unrecognized { .. } => {
+                match unknown_command(payload) {
+                    // only the failing payloads are decoded a second time
+                    Some(_) => Some(Self::UnknownCommand),
+                    None => Some(Self::InvalidJson),
+                }
+            }
+        }
+    }
+}
+
+/// What happens with the error of a protocol violation after the
+/// [`Server::on_violation`] callback is invoked.
+///
+/// [`Server::on_violation`]: crate::Server::on_violation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ViolationAction {
+    /// The error is yielded by the [`Server`](crate::Server) stream.
+    #[default]
+    Surface,
+    /// The error is skipped and the [`Server`](crate::Server) stream continues
+    /// with the next received command.
+    Suppress,
+}
+
+type ViolationCallback = dyn Fn(ViolationKind, &str) + Send + Sync;
+
+/// The callback and the action of the reported protocol violations.
+#[derive(Clone)]
+pub(crate) struct ViolationHandler {
+    pub(crate) callback: Arc<ViolationCallback>,
+    pub(crate) action: ViolationAction,
+}
+
+impl ViolationHandler {
+    /// Reports the violation and returns whether its error should be suppressed.
+    pub(crate) fn report(&self, kind: ViolationKind, payload: &str) -> bool {
+        (self.callback)(kind, payload);
+
+        self.action == ViolationAction::Suppress
+    }
+}
+
+impl core::fmt::Debug for ViolationHandler {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ViolationHandler")
+            .field("action", &self.action)
+            .finish_non_exhaustive()
+    }
+}
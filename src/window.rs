@@ -0,0 +1,135 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::protocol::{is_valid_label, InvalidLabel};
+
+/// The last `N` values of every series (label), e.g. for plotting the moving average
+/// of a noisy sensor.
+///
+/// The series are kept in the order they were first pushed.
+///
+/// ```
+/// use arduino_plotter::{protocol::InvalidLabel, SlidingWindow};
+///
+/// let mut window = SlidingWindow::new(3);
+/// for value in [1.0, 2.0, 3.0, 4.0] {
+///     window.push("temp", value).unwrap();
+/// }
+/// window.push("humidity", 40.0).unwrap();
+///
+/// // a label which would corrupt the line is never kept
+/// assert_eq!(
+///     Err(InvalidLabel("air temp".to_string())),
+///     window.push("air temp", 21.5)
+/// );
+///
+/// // only the last 3 values are kept
+/// assert_eq!(Some(3.0), window.mean("temp"));
+/// assert_eq!(Some(4.0), window.latest("temp"));
+/// assert_eq!(Some(40.0), window.mean("humidity"));
+/// assert_eq!(None, window.mean("pressure"));
+///
+/// assert_eq!(Some("temp:3,humidity:40\n".to_string()), window.means_line());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlidingWindow {
+    capacity: usize,
+    /// The index of every label in the `series`.
+    indices: HashMap<String, usize>,
+    series: Vec<(String, VecDeque<f64>)>,
+}
+
+impl SlidingWindow {
+    /// Creates a window keeping the last `capacity` values of every series.
+    ///
+    /// # Panics
+    ///
+    /// When `capacity` is `0`.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Window capacity should be greater than 0");
+
+        Self {
+            capacity,
+            indices: HashMap::new(),
+            series: Vec::new(),
+        }
+    }
+
+    /// The number of values kept for every series.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Pushes the latest `value` of the `label` series evicting its oldest value
+    /// when the window is full.
+    ///
+    /// Fails with an [`InvalidLabel`] without pushing the value when the label is empty
+    /// or contains a label (`:`) or values (`,`, ` `, `\t`) separator,
+    /// so [`SlidingWindow::means_line`] always is a valid frame.
+    pub fn push(&mut self, label: &str, value: f64) -> Result<(), InvalidLabel> {
+        let index = match self.indices.get(label) {
+            Some(index) => *index,
+            None => {
+                if !is_valid_label(label) {
+                    return Err(InvalidLabel(label.to_string()));
+                }
+
+                self.series
+                    .push((label.to_string(), VecDeque::with_capacity(self.capacity)));
+                self.indices
+                    .insert(label.to_string(), self.series.len() - 1);
+
+                self.series.len() - 1
+            }
+        };
+
+        let values = &mut self.series[index].1;
+        if values.len() == self.capacity {
+            values.pop_front();
+        }
+        values.push_back(value);
+
+        Ok(())
+    }
+
+    /// The mean of the values in the window of the `label` series.
+    pub fn mean(&self, label: &str) -> Option<f64> {
+        let values = self.values(label)?;
+
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    /// The latest value of the `label` series.
+    pub fn latest(&self, label: &str) -> Option<f64> {
+        self.values(label)?.back().copied()
+    }
+
+    /// The labels of the series in the order they were first pushed.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.series.iter().map(|(label, _)| label.as_str())
+    }
+
+    /// A frame (a single Data line) with the mean of every series,
+    /// e.g. `temp:21.5,humidity:40\n`, or `None` when nothing has been pushed.
+    pub fn means_line(&self) -> Option<String> {
+        if self.series.is_empty() {
+            return None;
+        }
+
+        let line = self
+            .series
+            .iter()
+            .map(|(label, values)| {
+                let mean = values.iter().sum::<f64>() / values.len() as f64;
+
+                format!("{label}:{mean}")
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Some(line + "\n")
+    }
+
+    fn values(&self, label: &str) -> Option<&VecDeque<f64>> {
+        self.indices.get(label).map(|index| &self.series[*index].1)
+    }
+}